@@ -0,0 +1,5 @@
+CREATE TABLE LoginAttempts(
+  attempt_key  VARCHAR(255) PRIMARY KEY NOT NULL,
+  failures     INTEGER NOT NULL,
+  locked_until DATETIME
+);
@@ -0,0 +1,33 @@
+CREATE TABLE Households(
+  id   INTEGER PRIMARY KEY NOT NULL,
+  name VARCHAR(255) NOT NULL
+);
+
+CREATE TABLE HouseholdMembers(
+  household_id INTEGER NOT NULL
+    REFERENCES Households(id) ON DELETE CASCADE,
+  account_id   INTEGER NOT NULL
+    REFERENCES Accounts(id) ON DELETE CASCADE,
+  role         VARCHAR(16) NOT NULL,
+  PRIMARY KEY (household_id, account_id)
+);
+
+CREATE TABLE HouseholdInvitations(
+  code         VARCHAR(64) PRIMARY KEY NOT NULL,
+  household_id INTEGER NOT NULL
+    REFERENCES Households(id) ON DELETE CASCADE,
+  role         VARCHAR(16) NOT NULL,
+  expires_at   DATETIME NOT NULL
+);
+
+ALTER TABLE Devices ADD COLUMN household_id INTEGER
+  REFERENCES Households(id) ON DELETE CASCADE;
+
+-- Every existing account gets a personal household owning its devices
+INSERT INTO Households (id, name)
+SELECT id, username FROM Accounts;
+
+INSERT INTO HouseholdMembers (household_id, account_id, role)
+SELECT id, id, 'owner' FROM Accounts;
+
+UPDATE Devices SET household_id = account_id;
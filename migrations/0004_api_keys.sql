@@ -0,0 +1,10 @@
+CREATE TABLE ApiKeys(
+  id         INTEGER PRIMARY KEY NOT NULL,
+  key_hash   VARCHAR(64) NOT NULL,
+  name       VARCHAR(255) NOT NULL,
+  scopes     VARCHAR(255) NOT NULL,
+  created_at DATETIME NOT NULL,
+  account_id INTEGER NOT NULL
+    REFERENCES Accounts(id) ON DELETE CASCADE,
+  UNIQUE(key_hash)
+);
@@ -0,0 +1,3 @@
+-- Administrators are appointed directly in the database
+ALTER TABLE Accounts ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE Accounts ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT FALSE;
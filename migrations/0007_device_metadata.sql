@@ -0,0 +1,7 @@
+ALTER TABLE Devices ADD COLUMN name VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE Devices ADD COLUMN device_type VARCHAR(32) NOT NULL DEFAULT 'other';
+ALTER TABLE Devices ADD COLUMN manufacturer VARCHAR(255);
+ALTER TABLE Devices ADD COLUMN model VARCHAR(255);
+ALTER TABLE Devices ADD COLUMN location VARCHAR(255);
+
+UPDATE Devices SET name = 'Device ' || id;
@@ -0,0 +1,16 @@
+-- Daily windows in UTC during which a device may run, a device without any may always run
+CREATE TABLE DeviceAllowedHours(
+  id         INTEGER PRIMARY KEY NOT NULL,
+  device_id  INTEGER NOT NULL
+    REFERENCES Devices(id) ON DELETE CASCADE,
+  start_time TIME NOT NULL,
+  end_time   TIME NOT NULL
+);
+
+CREATE TABLE DeviceBlackouts(
+  id             INTEGER PRIMARY KEY NOT NULL,
+  device_id      INTEGER NOT NULL
+    REFERENCES Devices(id) ON DELETE CASCADE,
+  timespan_start DATETIME NOT NULL,
+  timespan_end   DATETIME NOT NULL
+);
@@ -0,0 +1,16 @@
+-- Devices of which at most max_concurrent may run at the same time, e.g. because they share a circuit
+CREATE TABLE ExclusionGroups(
+  id             INTEGER PRIMARY KEY NOT NULL,
+  household_id   INTEGER NOT NULL
+    REFERENCES Households(id) ON DELETE CASCADE,
+  name           VARCHAR(255) NOT NULL,
+  max_concurrent INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE TABLE ExclusionGroupDevices(
+  group_id  INTEGER NOT NULL
+    REFERENCES ExclusionGroups(id) ON DELETE CASCADE,
+  device_id INTEGER NOT NULL
+    REFERENCES Devices(id) ON DELETE CASCADE,
+  PRIMARY KEY (group_id, device_id)
+);
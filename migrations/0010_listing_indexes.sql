@@ -0,0 +1,4 @@
+-- Listings filter tasks by device and time range, and devices by household
+CREATE INDEX TasksByDevice ON Tasks(device_id, timespan_start);
+CREATE INDEX TasksByEnd ON Tasks(timespan_end);
+CREATE INDEX DevicesByHousehold ON Devices(household_id);
@@ -0,0 +1,12 @@
+ALTER TABLE Tasks ADD COLUMN title VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE Tasks ADD COLUMN notes TEXT;
+
+-- Tags compare case-insensitively, so "Laundry" and "laundry" group together
+CREATE TABLE TaskTags(
+  task_id INTEGER NOT NULL
+    REFERENCES Tasks(id) ON DELETE CASCADE,
+  tag     VARCHAR(64) NOT NULL COLLATE NOCASE,
+  PRIMARY KEY (task_id, tag)
+);
+
+CREATE INDEX TaskTagsByTag ON TaskTags(tag);
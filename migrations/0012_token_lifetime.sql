@@ -0,0 +1,5 @@
+-- Tokens expire a configured time after they were issued
+ALTER TABLE AuthTokens ADD COLUMN created_at DATETIME NOT NULL DEFAULT '1970-01-01 00:00:00';
+
+-- Existing tokens count as issued now, rather than expiring all at once
+UPDATE AuthTokens SET created_at = CURRENT_TIMESTAMP;
@@ -0,0 +1,7 @@
+-- Failures are forgotten once an attempt key has had none for a while
+ALTER TABLE LoginAttempts ADD COLUMN last_failure DATETIME NOT NULL DEFAULT '1970-01-01T00:00:00+00:00';
+
+-- Written in the same format as the timestamps bound by the application, so they compare as text
+UPDATE LoginAttempts SET last_failure = strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now');
+
+CREATE INDEX LoginAttemptsByLastFailure ON LoginAttempts(last_failure);
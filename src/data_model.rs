@@ -1,4 +1,8 @@
+pub mod api_key;
+pub mod availability;
 pub mod device;
-mod event;
+pub mod event;
+pub mod exclusion_group;
+pub mod household;
 pub mod task;
 pub mod time;
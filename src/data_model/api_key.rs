@@ -0,0 +1,64 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::time::DateTimeUtc;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, ToSchema)]
+pub struct ApiKey {
+    pub id: i64,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    ReadTasks,
+    WriteTasks,
+    ManageDevices,
+    // The computed schedule, i.e. events and conflict checks
+    ReadReports,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::ReadTasks => "read_tasks",
+            Scope::WriteTasks => "write_tasks",
+            Scope::ManageDevices => "manage_devices",
+            Scope::ReadReports => "read_reports",
+        }
+    }
+
+    /// Formats scopes the way they are stored in the `ApiKeys.scopes` column.
+    pub fn join(scopes: &[Scope]) -> String {
+        scopes
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Parses the `ApiKeys.scopes` column, skipping scopes this version does not know.
+    pub fn split(scopes: &str) -> Vec<Scope> {
+        scopes.split(',').filter_map(|s| s.parse().ok()).collect()
+    }
+}
+
+impl FromStr for Scope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read_tasks" => Ok(Scope::ReadTasks),
+            "write_tasks" => Ok(Scope::WriteTasks),
+            "manage_devices" => Ok(Scope::ManageDevices),
+            "read_reports" => Ok(Scope::ReadReports),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::{Duration, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::time::{DateTimeUtc, Timespan};
+
+/// A daily window in UTC, running past midnight when `end` is not after `start`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+pub struct AllowedHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, ToSchema)]
+pub struct DeviceAvailability {
+    // A device without any allowed hours may run at any time of day
+    pub allowed_hours: Vec<AllowedHours>,
+    pub blackouts: Vec<Timespan>,
+}
+
+impl DeviceAvailability {
+    /// The earliest run of `duration` that fits within `timespan`, if any does.
+    pub fn earliest_run(&self, timespan: &Timespan, duration: Duration) -> Option<Timespan> {
+        let windows = self.windows_covering(timespan);
+
+        // A run can only be blocked until a window opens or a blackout ends
+        let mut candidates: Vec<DateTimeUtc> = std::iter::once(timespan.start)
+            .chain(windows.iter().map(|w| w.start))
+            .chain(self.blackouts.iter().map(|b| b.end))
+            .filter(|&start| start >= timespan.start)
+            .collect();
+        candidates.sort();
+
+        candidates
+            .into_iter()
+            // Runs ending past the last representable time cannot fit in any timespan
+            .filter_map(|start| Some(Timespan::new(start, start.checked_add_signed(duration)?)))
+            .find(|run| timespan.contains(run) && self.allows(run, &windows))
+    }
+
+    fn allows(&self, run: &Timespan, windows: &[Timespan]) -> bool {
+        let within_hours = self.allowed_hours.is_empty() || windows.iter().any(|w| w.contains(run));
+        within_hours && !self.blackouts.iter().any(|b| b.overlaps(run))
+    }
+
+    // The allowed hours of every day around `timespan`, with touching windows merged
+    fn windows_covering(&self, timespan: &Timespan) -> Vec<Timespan> {
+        let mut windows = Vec::new();
+
+        let days = timespan
+            .start
+            .date_naive()
+            .iter_days()
+            .take_while(|day| *day <= timespan.end.date_naive());
+        let previous_day = timespan.start.date_naive().pred_opt();
+
+        for day in previous_day.into_iter().chain(days) {
+            for hours in &self.allowed_hours {
+                let end_day = if hours.end <= hours.start {
+                    day.succ_opt().unwrap_or(NaiveDate::MAX)
+                } else {
+                    day
+                };
+
+                windows.push(Timespan::new(
+                    day.and_time(hours.start).and_utc(),
+                    end_day.and_time(hours.end).and_utc(),
+                ));
+            }
+        }
+
+        windows.sort_by_key(|w| w.start);
+
+        let mut merged: Vec<Timespan> = Vec::with_capacity(windows.len());
+        for window in windows {
+            match merged.last_mut() {
+                Some(last) if window.start <= last.end => last.end = last.end.max(window.end),
+                _ => merged.push(window),
+            }
+        }
+
+        merged
+    }
+}
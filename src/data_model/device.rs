@@ -1,8 +1,80 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize)]
+use super::time::Milliseconds;
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct Device {
     pub id: i64,
+    pub name: String,
+    pub device_type: DeviceType,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    // Room or other place in the home, called `location` in the first version
+    pub room: Option<String>,
     pub effect: f64,
+    // The account that registered the device, access is granted through the household
     pub account_id: i64,
+    pub household_id: i64,
+    pub defaults: DeviceTypeDefaults,
+}
+
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum DeviceType {
+    Washer,
+    Dryer,
+    Dishwasher,
+    EvCharger,
+    HeatPump,
+    WaterHeater,
+    #[default]
+    Other,
+}
+
+/// What a device of a given type usually looks like, for clients to prefill tasks with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct DeviceTypeDefaults {
+    pub typical_duration: Milliseconds,
+    // Whether a run may be paused and resumed without harm
+    pub interruptible: bool,
+    // Share of the device's effect drawn in each equally long part of a run
+    pub power_profile: Vec<f64>,
+}
+
+impl DeviceType {
+    pub fn label(self) -> &'static str {
+        match self {
+            DeviceType::Washer => "Washer",
+            DeviceType::Dryer => "Dryer",
+            DeviceType::Dishwasher => "Dishwasher",
+            DeviceType::EvCharger => "EV charger",
+            DeviceType::HeatPump => "Heat pump",
+            DeviceType::WaterHeater => "Water heater",
+            DeviceType::Other => "Device",
+        }
+    }
+
+    pub fn defaults(self) -> DeviceTypeDefaults {
+        const MINUTE: i64 = 60 * 1000;
+
+        let (minutes, interruptible, power_profile) = match self {
+            DeviceType::Washer => (120, false, vec![1.0, 0.3, 0.2, 0.6]),
+            DeviceType::Dryer => (90, false, vec![1.0, 1.0, 0.8]),
+            DeviceType::Dishwasher => (150, false, vec![0.9, 0.2, 1.0, 0.1]),
+            DeviceType::EvCharger => (360, true, vec![1.0]),
+            DeviceType::HeatPump => (60, true, vec![0.8, 1.0, 0.8]),
+            DeviceType::WaterHeater => (120, true, vec![1.0]),
+            DeviceType::Other => (60, false, vec![1.0]),
+        };
+
+        DeviceTypeDefaults {
+            typical_duration: (minutes * MINUTE).into(),
+            interruptible,
+            power_profile,
+        }
+    }
 }
@@ -1,11 +1,12 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use super::time::DateTimeUtc;
 
-#[derive(Serialize, Deserialize)]
-struct Event {
-    id: i64,
-    device_id: i64,
-    version_nr: i64,
-    start_time: DateTimeUtc,
+#[derive(Serialize, Deserialize, Debug, PartialEq, ToSchema)]
+pub struct Event {
+    pub id: i64,
+    pub task_id: i64,
+    #[schema(value_type = String, format = DateTime)]
+    pub start_time: DateTimeUtc,
 }
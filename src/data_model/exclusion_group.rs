@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::time::Timespan;
+
+/// Devices of which at most `max_concurrent` may run at the same time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ExclusionGroup {
+    pub id: i64,
+    pub household_id: i64,
+    pub name: String,
+    pub max_concurrent: i64,
+    pub device_ids: Vec<i64>,
+}
+
+impl ExclusionGroup {
+    /// Whether another device of the group may run during `run`, given the `runs` already planned.
+    pub fn admits(&self, runs: &[Timespan], run: &Timespan) -> bool {
+        peak_concurrency(runs, run) < self.max_concurrent
+    }
+}
+
+// The most of `runs` that are running at any one instant during `within`
+fn peak_concurrency(runs: &[Timespan], within: &Timespan) -> i64 {
+    let mut changes: Vec<_> = runs
+        .iter()
+        .filter(|r| r.overlaps(within))
+        .flat_map(|r| [(r.start.max(within.start), 1), (r.end.min(within.end), -1)])
+        .collect();
+    // Runs ending as another starts are not concurrent, so ends sort first
+    changes.sort();
+
+    changes
+        .into_iter()
+        .scan(0, |running, (_, change)| {
+            *running += change;
+            Some(*running)
+        })
+        .max()
+        .unwrap_or(0)
+}
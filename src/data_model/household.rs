@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, ToSchema)]
+pub struct Household {
+    pub id: i64,
+    pub name: String,
+}
+
+// Ordered by increasing rights, so a role can be compared against the least one required
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Member,
+    Owner,
+}
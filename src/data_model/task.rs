@@ -1,11 +1,29 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use super::time::{Milliseconds, Timespan};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, ToSchema)]
 pub struct Task {
     pub id: i64,
     pub timespan: Timespan,
     pub duration: Milliseconds,
     pub device_id: i64,
+    pub title: String,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Where a task is in its life, derived from its timespan and scheduled event.
+#[derive(Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum TaskStatus {
+    // Not scheduled yet, but there is still time to
+    Pending,
+    Scheduled,
+    Running,
+    Completed,
+    // The timespan ended without the task ever being scheduled
+    Missed,
 }
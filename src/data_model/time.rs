@@ -1,15 +1,18 @@
 use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 pub type DateTimeUtc = DateTime<Utc>;
 
-#[derive(Serialize, Deserialize, sqlx::Type, Debug, PartialEq, Eq)]
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema,
+)]
 #[sqlx(transparent)]
 pub struct Milliseconds(i64);
 
-impl Into<Duration> for Milliseconds {
-    fn into(self) -> Duration {
-        Duration::try_milliseconds(self.0).unwrap()
+impl From<Milliseconds> for Duration {
+    fn from(value: Milliseconds) -> Self {
+        Duration::try_milliseconds(value.0).unwrap()
     }
 }
 
@@ -25,9 +28,11 @@ impl From<i64> for Milliseconds {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
 pub struct Timespan {
+    #[schema(value_type = String, format = DateTime)]
     pub start: DateTimeUtc,
+    #[schema(value_type = String, format = DateTime)]
     pub end: DateTimeUtc,
 }
 
@@ -36,6 +41,18 @@ impl Timespan {
         Timespan { start, end }
     }
 
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    pub fn contains(&self, other: &Timespan) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Timespan) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
     pub fn new_from_naive(start: NaiveDateTime, end: NaiveDateTime) -> Self {
         fn to_utc(date_time: NaiveDateTime) -> DateTimeUtc {
             Utc::from_utc_datetime(&Utc, &date_time)
@@ -0,0 +1,233 @@
+use std::{error::Error, time::Duration};
+
+use argon2::password_hash;
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::middleware::request_id::current_request_id;
+
+/// Everything a request can fail with. Responds with an [`ErrorBody`], internal
+/// details are logged rather than sent to the client.
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    // Input that failed validation, by field
+    Validation(Vec<FieldError>),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    NotAcceptable(String),
+    Conflict(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    RequestTimeout(String),
+    TooManyRequests {
+        message: String,
+        retry_after: Duration,
+    },
+    Internal(Box<dyn Error + Send + Sync>),
+}
+
+/// What clients receive when a request fails.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ErrorBody {
+    // Stable and machine-readable, unlike the message
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl AppError {
+    /// A validation failure of a single field.
+    pub fn invalid(field: &str, message: impl Into<String>) -> Self {
+        AppError::Validation(vec![FieldError {
+            field: field.to_string(),
+            code: "invalid".to_string(),
+            message: message.into(),
+        }])
+    }
+
+    /// Tells the client why it is refused and, in both the message and `Retry-After`, how
+    /// many seconds to wait.
+    pub fn too_many_requests(reason: &str, retry_after: Duration) -> Self {
+        let message = match retry_after_secs(retry_after) {
+            1 => format!("{reason}, try again in 1 second"),
+            seconds => format!("{reason}, try again in {seconds} seconds"),
+        };
+
+        AppError::TooManyRequests {
+            message,
+            retry_after,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::NotAcceptable(_) => StatusCode::NOT_ACCEPTABLE,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Validation(_) => "validation_failed",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::NotAcceptable(_) => "not_acceptable",
+            AppError::Conflict(_) => "conflict",
+            AppError::PayloadTooLarge(_) => "payload_too_large",
+            AppError::UnsupportedMediaType(_) => "unsupported_media_type",
+            AppError::RequestTimeout(_) => "request_timeout",
+            AppError::TooManyRequests { .. } => "too_many_requests",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::NotAcceptable(message)
+            | AppError::Conflict(message)
+            | AppError::PayloadTooLarge(message)
+            | AppError::UnsupportedMediaType(message)
+            | AppError::RequestTimeout(message)
+            | AppError::TooManyRequests { message, .. } => message.clone(),
+            AppError::Validation(fields) => match fields.as_slice() {
+                [field] => field.message.clone(),
+                _ => format!("{} fields are invalid", fields.len()),
+            },
+            AppError::Internal(_) => "Something went wrong on our side".to_string(),
+        }
+    }
+
+    /// Describes this error as belonging to `field`, e.g. an item of a batch.
+    /// Fields of validation errors are nested below it.
+    pub fn into_field_errors(self, field: &str) -> Vec<FieldError> {
+        match self {
+            AppError::Validation(fields) => fields
+                .into_iter()
+                .map(|f| FieldError {
+                    field: format!("{field}.{}", f.field),
+                    ..f
+                })
+                .collect(),
+            error => vec![FieldError {
+                field: field.to_string(),
+                code: error.code().to_string(),
+                message: error.message(),
+            }],
+        }
+    }
+}
+
+// Failures of the database or of hashing are unexpected, so they are reported as internal
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        AppError::Internal(Box::new(error))
+    }
+}
+
+impl From<password_hash::Error> for AppError {
+    fn from(error: password_hash::Error) -> Self {
+        AppError::Internal(Box::new(error))
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(rejection.body_text()),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => {
+                AppError::UnsupportedMediaType(rejection.body_text())
+            }
+            status if status.is_server_error() => AppError::Internal(Box::new(rejection)),
+            // Syntax errors as well as well formed bodies of the wrong shape
+            _ => AppError::BadRequest(rejection.body_text()),
+        }
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        match rejection.status() {
+            // The route and handler disagree on the parameters, which is a bug on our side
+            status if status.is_server_error() => AppError::Internal(Box::new(rejection)),
+            _ => AppError::BadRequest(rejection.body_text()),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let request_id = current_request_id();
+
+        // The request span this is logged in already carries the request id
+        if let AppError::Internal(error) = &self {
+            tracing::error!(%error, "Request failed");
+        }
+
+        let body = ErrorBody {
+            code: self.code().to_string(),
+            message: self.message(),
+            fields: Vec::new(),
+            request_id,
+        };
+        let status = self.status();
+
+        match self {
+            AppError::Validation(fields) => {
+                (status, Json(ErrorBody { fields, ..body })).into_response()
+            }
+            AppError::TooManyRequests { retry_after, .. } => {
+                let mut response = (status, Json(body)).into_response();
+                response.headers_mut().insert(
+                    header::RETRY_AFTER,
+                    HeaderValue::from(retry_after_secs(retry_after)),
+                );
+                response
+            }
+            _ => (status, Json(body)).into_response(),
+        }
+    }
+}
+
+// Rounded up, so retrying right away is never too early
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
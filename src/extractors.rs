@@ -1 +1,5 @@
 pub mod auth;
+pub mod json;
+pub mod path;
+pub mod query;
+pub mod version;
@@ -1,13 +1,23 @@
+use std::sync::Arc;
+
 use axum::{
     async_trait,
-    extract::FromRequestParts,
-    http::{request::Parts, HeaderMap, StatusCode},
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, HeaderMap},
 };
+use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::SqlitePool;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Deserialize, Serialize, sqlx::Type)]
+use crate::{
+    config::Config, data_model::api_key::Scope, error::AppError,
+    middleware::trace::record_account_id,
+};
+
+#[derive(Deserialize, Serialize, sqlx::Type, ToSchema)]
 #[sqlx(transparent)]
 pub struct AuthToken(Uuid);
 
@@ -22,53 +32,179 @@ impl AuthToken {
     }
 }
 
-// Account id
-pub struct Authentication(pub i64);
+// Account id and what the credentials used are allowed to do
+#[derive(Clone)]
+pub struct Authentication(pub i64, pub Access);
 
-#[async_trait]
-impl FromRequestParts<SqlitePool> for Authentication {
-    type Rejection = (StatusCode, String);
-
-    async fn from_request_parts(
-        parts: &mut Parts,
-        pool: &SqlitePool,
-    ) -> Result<Self, Self::Rejection> {
-        match get_auth_token(&parts.headers) {
-            Some(token) => {
-                if let Some(account_id) = get_account_id_from_token(token, pool).await {
-                    Ok(Authentication(account_id))
-                } else {
-                    Err((
-                        StatusCode::UNAUTHORIZED,
-                        "Auth token is not in the database".to_string(),
-                    ))
-                }
+// What authenticating a request came to, kept in its extensions so that middleware and
+// handlers extracting `Authentication` share one lookup
+#[derive(Clone)]
+struct Resolved(Result<Authentication, String>);
+
+#[derive(Clone)]
+pub enum Access {
+    // Logged in with a password, which grants everything
+    Session,
+    ApiKey(Vec<Scope>),
+}
+
+impl Access {
+    pub fn require(&self, scope: Scope) -> Result<(), AppError> {
+        let allowed = match self {
+            Access::Session => true,
+            Access::ApiKey(scopes) => {
+                scopes.contains(&scope)
+                    || (scope == Scope::ReadTasks && scopes.contains(&Scope::WriteTasks))
             }
-            _ => Err((
-                StatusCode::UNAUTHORIZED,
-                "Auth token invalid or missing".to_string(),
+        };
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!(
+                "API key lacks the {} scope",
+                scope.as_str()
+            )))
+        }
+    }
+
+    pub fn require_session(&self) -> Result<(), AppError> {
+        match self {
+            Access::Session => Ok(()),
+            Access::ApiKey(_) => Err(AppError::Forbidden(
+                "This requires logging in, API keys are not accepted".to_string(),
             )),
         }
     }
 }
 
+#[async_trait]
+impl<S> FromRequestParts<S> for Authentication
+where
+    SqlitePool: FromRef<S>,
+    Arc<Config>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let resolved = match parts.extensions.get::<Resolved>() {
+            Some(resolved) => resolved.clone(),
+            None => {
+                let resolved = Resolved(authenticate(parts, state).await);
+                parts.extensions.insert(resolved.clone());
+                resolved
+            }
+        };
+
+        resolved.0.map_err(AppError::Unauthorized)
+    }
+}
+
+// Rejections are the message to respond 401 with
+async fn authenticate<S>(parts: &Parts, state: &S) -> Result<Authentication, String>
+where
+    SqlitePool: FromRef<S>,
+    Arc<Config>: FromRef<S>,
+{
+    let pool = SqlitePool::from_ref(state);
+
+    if let Some(api_key) = parts.headers.get("X-Api-Key") {
+        let api_key = api_key
+            .to_str()
+            .map_err(|_| "API key invalid or missing".to_string())?;
+
+        return match get_api_key_access(api_key, &pool).await {
+            Some((account_id, scopes)) => {
+                record_account_id(account_id);
+                Ok(Authentication(account_id, Access::ApiKey(scopes)))
+            }
+            None => Err("API key is not in the database".to_string()),
+        };
+    }
+
+    match get_auth_token(&parts.headers) {
+        Some(token) => {
+            let config = Arc::<Config>::from_ref(state);
+            let account_id = get_account_id_from_token(token, config.token_lifetime(), &pool).await;
+
+            if let Some(account_id) = account_id {
+                record_account_id(account_id);
+                Ok(Authentication(account_id, Access::Session))
+            } else {
+                Err("Auth token is not in the database or has expired".to_string())
+            }
+        }
+        _ => Err("Auth token invalid or missing".to_string()),
+    }
+}
+
+// Account id of an administrator logged in with a password
+pub struct AdminAuthentication(pub i64);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminAuthentication
+where
+    SqlitePool: FromRef<S>,
+    Arc<Config>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Authentication(account_id, access) =
+            Authentication::from_request_parts(parts, state).await?;
+        access.require_session()?;
+
+        let pool = SqlitePool::from_ref(state);
+        let is_admin = sqlx::query_scalar!(
+            r#"
+            SELECT is_admin
+            FROM Accounts
+            WHERE id = ?
+            "#,
+            account_id
+        )
+        .fetch_one(&pool)
+        .await?;
+
+        if is_admin {
+            Ok(AdminAuthentication(account_id))
+        } else {
+            Err(AppError::Forbidden(
+                "This requires an administrator".to_string(),
+            ))
+        }
+    }
+}
+
 fn get_auth_token(headers: &HeaderMap) -> Option<AuthToken> {
     let string = headers.get("X-Auth-Token")?.to_str().ok()?;
     AuthToken::try_parse(string).ok()
 }
 
-async fn get_account_id_from_token(token: AuthToken, pool: &SqlitePool) -> Option<i64> {
-    sqlx::query_scalar!(
+async fn get_account_id_from_token(
+    token: AuthToken,
+    lifetime: Duration,
+    pool: &SqlitePool,
+) -> Option<i64> {
+    let token = sqlx::query!(
         r#"
-        SELECT account_id
+        SELECT AuthTokens.account_id, AuthTokens.created_at
         FROM AuthTokens
-        WHERE id = ?
+        JOIN Accounts ON AuthTokens.account_id == Accounts.id
+        WHERE AuthTokens.id = ? AND NOT Accounts.disabled
         "#,
         token
     )
     .fetch_optional(pool)
     .await
-    .ok()?
+    .ok()??;
+
+    let expires_at = token.created_at.and_utc().checked_add_signed(lifetime);
+    let expired = expires_at.is_some_and(|expires_at| expires_at <= Utc::now());
+
+    (!expired).then_some(token.account_id)
 }
 
 pub async fn create_auth_token(
@@ -77,16 +213,43 @@ pub async fn create_auth_token(
 ) -> Result<AuthToken, sqlx::Error> {
     let auth_token = AuthToken::new();
 
+    let created_at = Utc::now();
+
     sqlx::query!(
         r#"
-        INSERT INTO AuthTokens (id, account_id)
-        VALUES (?, ?)
+        INSERT INTO AuthTokens (id, account_id, created_at)
+        VALUES (?, ?, ?)
         "#,
         auth_token,
-        account_id
+        account_id,
+        created_at
     )
     .execute(pool)
     .await?;
 
     Ok(auth_token)
 }
+
+/// API keys are stored as their SHA-256 digest, so a leaked database does not leak keys.
+pub fn hash_api_key(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.as_bytes()))
+}
+
+async fn get_api_key_access(api_key: &str, pool: &SqlitePool) -> Option<(i64, Vec<Scope>)> {
+    let key_hash = hash_api_key(api_key);
+
+    let api_key = sqlx::query!(
+        r#"
+        SELECT ApiKeys.account_id, ApiKeys.scopes
+        FROM ApiKeys
+        JOIN Accounts ON ApiKeys.account_id == Accounts.id
+        WHERE ApiKeys.key_hash = ? AND NOT Accounts.disabled
+        "#,
+        key_hash
+    )
+    .fetch_optional(pool)
+    .await
+    .ok()??;
+
+    Some((api_key.account_id, Scope::split(&api_key.scopes)))
+}
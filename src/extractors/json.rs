@@ -0,0 +1,49 @@
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Request},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::{
+    error::AppError,
+    protocol::versioning::{ApiVersion, VersionedRequest},
+};
+
+/// [`axum::Json`], except that bodies it cannot read are rejected with an [`AppError`],
+/// so clients get the same error body as for any other failure.
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(AppError))]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// A JSON body read in the shape of the version the request is served under, then
+/// converted into the latest one.
+pub struct VersionedBody<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for VersionedBody<T>
+where
+    S: Send + Sync,
+    T: VersionedRequest,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = request.into_parts();
+        let version = ApiVersion::from_request_parts(&mut parts, state).await?;
+        let request = Request::from_parts(parts, body);
+
+        let body = match version {
+            ApiVersion::V1 => Json::<T::V1>::from_request(request, state).await?.0.into(),
+            ApiVersion::V2 => Json::<T>::from_request(request, state).await?.0,
+        };
+
+        Ok(VersionedBody(body))
+    }
+}
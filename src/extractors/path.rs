@@ -0,0 +1,8 @@
+use axum::extract::FromRequestParts;
+
+use crate::error::AppError;
+
+/// [`axum::extract::Path`], rejecting path parameters it cannot read with an [`AppError`].
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct Path<T>(pub T);
@@ -0,0 +1,8 @@
+use axum::extract::FromRequestParts;
+
+use crate::error::AppError;
+
+/// [`axum::extract::Query`], rejecting query strings it cannot read with an [`AppError`].
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(AppError))]
+pub struct Query<T>(pub T);
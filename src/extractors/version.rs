@@ -0,0 +1,54 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+
+use crate::{error::AppError, protocol::versioning::ApiVersion};
+
+// Media type prefix clients put in `Accept` to pick a version on unversioned routes
+const VENDOR_MEDIA_TYPE: &str = "application/vnd.scheduler.v";
+
+/// The version of the API a request is served under. Taken from the path prefix,
+/// otherwise negotiated through `Accept: application/vnd.scheduler.v2+json`.
+#[async_trait]
+impl<S> FromRequestParts<S> for ApiVersion
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Set by the router for routes nested under a version prefix
+        if let Some(version) = parts.extensions.get::<ApiVersion>() {
+            return Ok(*version);
+        }
+
+        let Some(accept) = parts.headers.get(header::ACCEPT) else {
+            return Ok(ApiVersion::DEFAULT);
+        };
+
+        let requested = accept
+            .to_str()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|media_type| {
+                let media_type = media_type.split(';').next()?.trim();
+                media_type
+                    .strip_prefix(VENDOR_MEDIA_TYPE)?
+                    .strip_suffix("+json")
+            })
+            .next();
+
+        match requested {
+            None => Ok(ApiVersion::DEFAULT),
+            Some(number) => number
+                .parse()
+                .ok()
+                .and_then(ApiVersion::from_number)
+                .ok_or_else(|| {
+                    AppError::NotAcceptable(format!("API version {number} does not exist"))
+                }),
+        }
+    }
+}
@@ -1,4 +1,12 @@
 pub mod accounts;
+pub mod admin;
+pub mod api_keys;
 pub mod devices;
+pub mod docs;
+pub mod events;
+pub mod exclusion_groups;
+pub mod health;
+pub mod households;
+pub mod metrics;
 pub mod tasks;
 pub mod util;
@@ -1,31 +1,41 @@
-use argon2::{
-    password_hash::{rand_core::OsRng, SaltString},
-    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+use std::net::SocketAddr;
+
+use axum::{
+    debug_handler,
+    extract::{ConnectInfo, State},
 };
-use axum::{debug_handler, extract::State, http::StatusCode, Json};
 use sqlx::SqlitePool;
 
 use crate::{
-    extractors::auth::create_auth_token,
-    handlers::util::internal_error,
+    error::AppError,
+    extractors::{auth::create_auth_token, json::Json},
+    handlers::households::insert_household,
+    metrics::{LoginOutcome, Metrics},
     protocol::accounts::{RegisterOrLoginRequest, RegisterOrLoginResponse},
+    security::{
+        login_attempts::{record_failure, record_success, remaining_lockout, AttemptKey},
+        password::PasswordHashing,
+    },
+    state::AppState,
 };
 
-#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/accounts/register",
+    tag = "accounts",
+    request_body = RegisterOrLoginRequest,
+    responses((status = 200, description = "The account was created and logged in", body = RegisterOrLoginResponse)),
+    security(()),
+)]
+#[debug_handler(state = AppState)]
 pub async fn register_account(
     State(pool): State<SqlitePool>,
+    State(password_hashing): State<PasswordHashing>,
     Json(register_request): Json<RegisterOrLoginRequest>,
-) -> Result<Json<RegisterOrLoginResponse>, (StatusCode, String)> {
-    let password = register_request.password;
-    let password_bytes = password.as_bytes();
-    let salt = SaltString::generate(&mut OsRng);
-
-    let argon2 = Argon2::default();
+) -> Result<Json<RegisterOrLoginResponse>, AppError> {
+    let password_hash = password_hashing.hash(&register_request.password)?;
 
-    let password_hash = argon2
-        .hash_password(password_bytes, &salt)
-        .map_err(internal_error)?
-        .to_string();
+    let mut tx = pool.begin().await?;
 
     let account_id = sqlx::query_scalar!(
         r#"
@@ -36,47 +46,109 @@ pub async fn register_account(
         register_request.username,
         password_hash
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(internal_error)?;
+    .map_err(|e| match e {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            AppError::Conflict("Username is already taken".to_string())
+        }
+        e => e.into(),
+    })?;
+
+    // Every account starts out with a personal household for its devices
+    insert_household(account_id, &register_request.username, &mut tx).await?;
 
-    let auth_token = create_auth_token(account_id, &pool)
-        .await
-        .map_err(internal_error)?;
+    tx.commit().await?;
+
+    let auth_token = create_auth_token(account_id, &pool).await?;
 
     Ok(Json(RegisterOrLoginResponse { auth_token }))
 }
 
-#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/accounts/login",
+    tag = "accounts",
+    request_body = RegisterOrLoginRequest,
+    responses((status = 200, description = "Logged in", body = RegisterOrLoginResponse)),
+    security(()),
+)]
+#[debug_handler(state = AppState)]
 pub async fn login_to_account(
     State(pool): State<SqlitePool>,
+    State(password_hashing): State<PasswordHashing>,
+    State(metrics): State<Metrics>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(login_request): Json<RegisterOrLoginRequest>,
-) -> Result<Json<RegisterOrLoginResponse>, (StatusCode, String)> {
+) -> Result<Json<RegisterOrLoginResponse>, AppError> {
+    let username_key = AttemptKey::username(&login_request.username);
+    let mut attempt_keys = vec![username_key.clone()];
+    if let Some(ConnectInfo(address)) = connect_info {
+        attempt_keys.push(AttemptKey::ip(address.ip()));
+    }
+
+    if let Some(remaining) = remaining_lockout(&attempt_keys, &pool).await? {
+        metrics.record_login(LoginOutcome::LockedOut);
+        return Err(AppError::too_many_requests(
+            "Too many failed login attempts",
+            remaining.to_std().unwrap_or_default(),
+        ));
+    }
+
     let account = sqlx::query!(
         r#"
-        SELECT id, password_hash
+        SELECT id, password_hash, disabled
         FROM Accounts
         WHERE username = ?
         "#,
         login_request.username
     )
     .fetch_optional(&pool)
-    .await
-    .map_err(internal_error)?;
+    .await?;
+
+    // Missing accounts are verified against a dummy hash, so both cases take as long
+    let verified = password_hashing.verify(
+        &login_request.password,
+        account.as_ref().map(|a| a.password_hash.as_str()),
+    )?;
+
+    let account = match account {
+        Some(account) if verified => account,
+        _ => {
+            metrics.record_login(LoginOutcome::Failure);
+            record_failure(&attempt_keys, &pool).await?;
+
+            return Err(AppError::Unauthorized(
+                "Invalid username or password".to_string(),
+            ));
+        }
+    };
+
+    if account.disabled {
+        metrics.record_login(LoginOutcome::Failure);
+        return Err(AppError::Forbidden("Account is disabled".to_string()));
+    }
 
-    let account = account.ok_or((
-        StatusCode::NOT_FOUND,
-        "No account with username exists".to_string(),
-    ))?;
+    metrics.record_login(LoginOutcome::Success);
+    record_success(&username_key, &pool).await?;
 
-    let password_hash = PasswordHash::new(&account.password_hash).map_err(internal_error)?;
+    // Upgrade hashes made with older parameters while the plaintext password is at hand
+    if password_hashing.needs_rehash(&account.password_hash)? {
+        let password_hash = password_hashing.hash(&login_request.password)?;
 
-    Argon2::default()
-        .verify_password(login_request.password.as_bytes(), &password_hash)
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid password".to_string()))?;
+        sqlx::query!(
+            r#"
+            UPDATE Accounts
+            SET password_hash = ?
+            WHERE id = ?
+            "#,
+            password_hash,
+            account.id
+        )
+        .execute(&pool)
+        .await?;
+    }
 
-    let auth_token = create_auth_token(account.id, &pool)
-        .await
-        .map_err(internal_error)?;
+    let auth_token = create_auth_token(account.id, &pool).await?;
     Ok(Json(RegisterOrLoginResponse { auth_token }))
 }
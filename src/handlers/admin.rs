@@ -0,0 +1,166 @@
+use axum::{debug_handler, extract::State, http::StatusCode};
+use sqlx::SqlitePool;
+
+use crate::{
+    error::AppError,
+    extractors::{auth::AdminAuthentication, json::Json},
+    protocol::admin::{AccountRequest, AccountSummary, RevokeTokensResponse},
+    scheduling::SchedulerHandle,
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/admin/accounts",
+    tag = "admin",
+    responses((status = 200, description = "Every account", body = [AccountSummary])),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_accounts(
+    State(pool): State<SqlitePool>,
+    AdminAuthentication(_): AdminAuthentication,
+) -> Result<Json<Vec<AccountSummary>>, AppError> {
+    let accounts = sqlx::query_as!(
+        AccountSummary,
+        r#"
+        SELECT
+            Accounts.id,
+            Accounts.username,
+            Accounts.is_admin,
+            Accounts.disabled,
+            (SELECT COUNT(*) FROM Devices WHERE Devices.account_id == Accounts.id) as "device_count!: i64",
+            (
+                SELECT COUNT(*)
+                FROM Tasks
+                JOIN Devices ON Tasks.device_id == Devices.id
+                WHERE Devices.account_id == Accounts.id
+            ) as "task_count!: i64"
+        FROM Accounts
+        ORDER BY Accounts.id
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(accounts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/accounts/disable",
+    tag = "admin",
+    request_body = AccountRequest,
+    responses((status = 200, description = "The account can no longer log in")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn disable_account(
+    State(pool): State<SqlitePool>,
+    AdminAuthentication(admin_id): AdminAuthentication,
+    Json(account_request): Json<AccountRequest>,
+) -> Result<(), AppError> {
+    // Otherwise the last administrator could lock everyone out
+    if account_request.account_id == admin_id {
+        return Err(AppError::BadRequest(
+            "Administrators cannot disable their own account".to_string(),
+        ));
+    }
+
+    set_disabled(account_request.account_id, true, &pool).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/accounts/enable",
+    tag = "admin",
+    request_body = AccountRequest,
+    responses((status = 200, description = "The account can log in again")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn enable_account(
+    State(pool): State<SqlitePool>,
+    AdminAuthentication(_): AdminAuthentication,
+    Json(account_request): Json<AccountRequest>,
+) -> Result<(), AppError> {
+    set_disabled(account_request.account_id, false, &pool).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/accounts/revoke-tokens",
+    tag = "admin",
+    request_body = AccountRequest,
+    responses((status = 200, description = "How many credentials were revoked", body = RevokeTokensResponse)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn revoke_account_tokens(
+    State(pool): State<SqlitePool>,
+    AdminAuthentication(_): AdminAuthentication,
+    Json(account_request): Json<AccountRequest>,
+) -> Result<Json<RevokeTokensResponse>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let auth_tokens = sqlx::query!(
+        r#"
+        DELETE FROM AuthTokens
+        WHERE account_id = ?
+        "#,
+        account_request.account_id
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let api_keys = sqlx::query!(
+        r#"
+        DELETE FROM ApiKeys
+        WHERE account_id = ?
+        "#,
+        account_request.account_id
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+
+    Ok(Json(RevokeTokensResponse {
+        auth_tokens,
+        api_keys,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/reschedule",
+    tag = "admin",
+    responses((status = 202, description = "The schedule will be recomputed shortly")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn trigger_reschedule(
+    State(scheduler): State<SchedulerHandle>,
+    AdminAuthentication(_): AdminAuthentication,
+) -> StatusCode {
+    scheduler.trigger();
+
+    StatusCode::ACCEPTED
+}
+
+async fn set_disabled(account_id: i64, disabled: bool, pool: &SqlitePool) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE Accounts
+        SET disabled = ?
+        WHERE id = ?
+        "#,
+        disabled,
+        account_id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("No account with id exists".to_string()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use axum::{debug_handler, extract::State};
+use chrono::{TimeZone, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    data_model::api_key::{ApiKey, Scope},
+    error::AppError,
+    extractors::{
+        auth::{hash_api_key, Authentication},
+        json::Json,
+    },
+    protocol::api_keys::{CreateApiKeyRequest, CreateApiKeyResponse, RevokeApiKeyRequest},
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api-keys/all",
+    tag = "api keys",
+    responses((status = 200, description = "The account's API keys", body = [ApiKey])),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_api_keys(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+) -> Result<Json<Vec<ApiKey>>, AppError> {
+    access.require_session()?;
+
+    let api_keys = sqlx::query!(
+        r#"
+        SELECT id, name, scopes, created_at
+        FROM ApiKeys
+        WHERE account_id = ?
+        "#,
+        account_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(
+        api_keys
+            .iter()
+            .map(|k| ApiKey {
+                id: k.id,
+                name: k.name.clone(),
+                scopes: Scope::split(&k.scopes),
+                created_at: Utc.from_utc_datetime(&k.created_at),
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api-keys/create",
+    tag = "api keys",
+    request_body = CreateApiKeyRequest,
+    responses((status = 200, description = "The created key, shown only this once", body = CreateApiKeyResponse)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn create_api_key(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Json(create_api_key_request): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreateApiKeyResponse>, AppError> {
+    access.require_session()?;
+
+    if create_api_key_request.scopes.is_empty() {
+        return Err(AppError::invalid(
+            "scopes",
+            "An API key needs at least one scope",
+        ));
+    }
+
+    let key = format!("sk_{}", Uuid::new_v4().simple());
+    let key_hash = hash_api_key(&key);
+    let scopes = Scope::join(&create_api_key_request.scopes);
+    let created_at = Utc::now();
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO ApiKeys (key_hash, name, scopes, created_at, account_id)
+        VALUES (?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+        key_hash,
+        create_api_key_request.name,
+        scopes,
+        created_at,
+        account_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(CreateApiKeyResponse {
+        api_key: ApiKey {
+            id,
+            name: create_api_key_request.name,
+            scopes: create_api_key_request.scopes,
+            created_at,
+        },
+        key,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api-keys/revoke",
+    tag = "api keys",
+    request_body = RevokeApiKeyRequest,
+    responses((status = 200, description = "The key was revoked")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn revoke_api_key(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Json(revoke_api_key_request): Json<RevokeApiKeyRequest>,
+) -> Result<(), AppError> {
+    access.require_session()?;
+
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM ApiKeys
+        WHERE id = ? AND account_id = ?
+        "#,
+        revoke_api_key_request.id,
+        account_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("No API key with id exists".to_string()));
+    }
+
+    Ok(())
+}
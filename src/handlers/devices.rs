@@ -1,85 +1,638 @@
-use axum::{debug_handler, extract::State, http::StatusCode, Json};
-use sqlx::SqlitePool;
+use axum::{
+    debug_handler,
+    extract::{OriginalUri, State},
+    http::StatusCode,
+};
+use chrono::NaiveTime;
+use sqlx::{SqliteConnection, SqlitePool};
 
 use crate::{
-    data_model::device::Device, extractors::auth::Authentication, handlers::util::internal_error,
-    protocol::devices::CreateDeviceRequest,
+    data_model::{
+        api_key::Scope,
+        availability::{AllowedHours, DeviceAvailability},
+        device::{Device, DeviceType},
+        household::Role,
+        time::Timespan,
+    },
+    error::AppError,
+    extractors::{
+        auth::{Access, Authentication},
+        json::{Json, VersionedBody},
+        path::Path,
+        query::Query,
+    },
+    handlers::util::{created, page, page_size, stale_cursor, Created, Page, VersionedJson},
+    protocol::{
+        devices::{CreateDeviceRequest, DeleteDeviceRequest, DeviceQuery, UpdateDeviceRequest},
+        versioning::ApiVersion,
+    },
+    scheduling::SchedulerHandle,
+    security::authorization::{device_role, household_role, require_role},
+    state::AppState,
 };
 
-#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/devices",
+    tag = "devices",
+    params(DeviceQuery),
+    responses((status = 200, description = "A page of devices, with a `Link` header to the next one if there is more", body = [Device])),
+)]
+#[debug_handler(state = AppState)]
 pub async fn get_all_smart_devices(
     State(pool): State<SqlitePool>,
-    Authentication(account_id): Authentication,
-) -> Result<Json<Vec<Device>>, (StatusCode, String)> {
-    let devices = sqlx::query!(
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<DeviceQuery>,
+) -> Result<Page<Device>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let page_size = page_size(query.limit)?;
+    // One more than fits the page, to tell whether there is a next one
+    let fetch_limit = page_size + 1;
+
+    // Read together with the page, so the cursor cannot disappear in between
+    let mut tx = pool.begin().await?;
+
+    // Cursors pointing at someone else's items are treated like missing ones
+    if let Some(cursor) = query.cursor {
+        let exists = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM Devices
+                JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+                WHERE Devices.id = ? AND HouseholdMembers.account_id = ?
+            ) as "exists!: bool"
+            "#,
+            cursor,
+            account_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if !exists {
+            return Err(stale_cursor());
+        }
+    }
+
+    let devices = sqlx::query_as!(
+        DeviceRow,
         r#"
-			SELECT id, effect, account_id
-			FROM Devices
-			WHERE account_id = ?
-		"#,
-        account_id
+        SELECT
+            Devices.id, Devices.name, Devices.device_type as "device_type: DeviceType",
+            Devices.manufacturer, Devices.model, Devices.location,
+            Devices.effect, Devices.account_id, Devices.household_id as "household_id!"
+        FROM Devices
+        JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ?1
+            AND (?2 IS NULL OR Devices.household_id = ?2)
+            AND (?3 IS NULL OR Devices.device_type = ?3)
+            AND (?6 IS NULL OR CASE ?5
+                WHEN 'asc' THEN
+                    (CASE ?4 WHEN 'name' THEN Devices.name ELSE Devices.id END, Devices.id)
+                    > (SELECT CASE ?4 WHEN 'name' THEN name ELSE id END, id FROM Devices WHERE id = ?6)
+                ELSE
+                    (CASE ?4 WHEN 'name' THEN Devices.name ELSE Devices.id END, Devices.id)
+                    < (SELECT CASE ?4 WHEN 'name' THEN name ELSE id END, id FROM Devices WHERE id = ?6)
+            END)
+        ORDER BY
+            CASE WHEN ?5 = 'asc' THEN CASE ?4 WHEN 'name' THEN Devices.name ELSE Devices.id END END ASC,
+            CASE WHEN ?5 = 'desc' THEN CASE ?4 WHEN 'name' THEN Devices.name ELSE Devices.id END END DESC,
+            CASE WHEN ?5 = 'asc' THEN Devices.id END ASC,
+            CASE WHEN ?5 = 'desc' THEN Devices.id END DESC
+        LIMIT ?7
+        "#,
+        account_id,
+        query.household_id,
+        query.device_type,
+        query.sort,
+        query.order,
+        query.cursor,
+        fetch_limit
     )
-    .fetch_all(&pool)
-    .await
-    .map_err(internal_error)?;
-
-    Ok(Json(
-        devices
-            .iter()
-            .map(|d| Device {
-                id: d.id,
-                effect: d.effect,
-                account_id: d.account_id,
-            })
-            .collect(),
-    ))
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let devices = devices.into_iter().map(Device::from).collect();
+
+    Ok(page(version, &uri, devices, page_size, |last: &Device| {
+        DeviceQuery {
+            cursor: Some(last.id),
+            ..query.clone()
+        }
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/device/all",
+    tag = "devices",
+    params(DeviceQuery),
+    responses((status = 200, description = "A page of devices, with a `Link` header to the next one if there is more", body = [Device])),
+)]
+#[deprecated = "Superseded by the resource routes"]
+#[debug_handler(state = AppState)]
+pub async fn get_all_devices(
+    state: State<SqlitePool>,
+    authentication: Authentication,
+    version: ApiVersion,
+    uri: OriginalUri,
+    query: Query<DeviceQuery>,
+) -> Result<Page<Device>, AppError> {
+    get_all_smart_devices(state, authentication, version, uri, query).await
 }
 
-#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/devices/{id}",
+    tag = "devices",
+    params(("id" = i64, Path, description = "Id of the device")),
+    responses((status = 200, description = "The device", body = Device)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_smart_device(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    Path(id): Path<i64>,
+) -> Result<VersionedJson<Device>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let device = sqlx::query_as!(
+        DeviceRow,
+        r#"
+        SELECT
+            Devices.id, Devices.name, Devices.device_type as "device_type: DeviceType",
+            Devices.manufacturer, Devices.model, Devices.location,
+            Devices.effect, Devices.account_id, Devices.household_id as "household_id!"
+        FROM Devices
+        JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ? AND Devices.id = ?
+        "#,
+        account_id,
+        id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
+
+    Ok(VersionedJson(version, device.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/device/create",
+    tag = "devices",
+    request_body = CreateDeviceRequest,
+    responses((status = 200, description = "The created device", body = Device)),
+)]
+#[deprecated = "Superseded by the resource routes"]
+#[debug_handler(state = AppState)]
 pub async fn create_smart_device(
     State(pool): State<SqlitePool>,
-    Authentication(account_id): Authentication,
-    Json(create_device_request): Json<CreateDeviceRequest>,
-) -> Result<Json<Device>, (StatusCode, String)> {
-    let id = sqlx::query_scalar!(
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    VersionedBody(create_device_request): VersionedBody<CreateDeviceRequest>,
+) -> Result<VersionedJson<Device>, AppError> {
+    let device = insert_device(account_id, &access, create_device_request, &pool).await?;
+
+    Ok(VersionedJson(version, device))
+}
+
+#[utoipa::path(
+    post,
+    path = "/devices",
+    tag = "devices",
+    request_body = CreateDeviceRequest,
+    responses((status = 201, description = "The created device", body = Device, headers(("Location" = String, description = "Where the created device can be found")))),
+)]
+#[debug_handler(state = AppState)]
+pub async fn post_smart_device(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    OriginalUri(uri): OriginalUri,
+    VersionedBody(create_device_request): VersionedBody<CreateDeviceRequest>,
+) -> Result<Created<VersionedJson<Device>>, AppError> {
+    let device = insert_device(account_id, &access, create_device_request, &pool).await?;
+
+    Ok(created(&uri, device.id, VersionedJson(version, device)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/devices/{id}",
+    tag = "devices",
+    params(("id" = i64, Path, description = "Id of the device")),
+    request_body = UpdateDeviceRequest,
+    responses((status = 200, description = "The updated device", body = Device)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn update_smart_device(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    Path(id): Path<i64>,
+    VersionedBody(update_device_request): VersionedBody<UpdateDeviceRequest>,
+) -> Result<VersionedJson<Device>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let role = device_role(account_id, id, &pool)
+        .await?
+        .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
+    require_role(role, Role::Member)?;
+
+    // Moving a device needs the same rights in the new household as creating it there
+    if let Some(household_id) = update_device_request.household_id {
+        let role = household_role(account_id, household_id, &pool)
+            .await?
+            .ok_or(AppError::NotFound(
+                "No household with id exists".to_string(),
+            ))?;
+        require_role(role, Role::Member)?;
+    }
+
+    let UpdateDeviceRequest {
+        effect,
+        name,
+        device_type,
+        manufacturer,
+        model,
+        room,
+        household_id,
+    } = update_device_request;
+    let (set_manufacturer, manufacturer) = (manufacturer.is_some(), manufacturer.flatten());
+    let (set_model, model) = (model.is_some(), model.flatten());
+    let (set_room, room) = (room.is_some(), room.flatten());
+
+    let mut tx = pool.begin().await?;
+
+    let device = sqlx::query_as!(
+        DeviceRow,
         r#"
-            INSERT INTO Devices (effect, account_id)
-            VALUES (?, ?)
-            RETURNING id
+        UPDATE Devices
+        SET
+            effect = COALESCE(?, effect),
+            name = COALESCE(?, name),
+            device_type = COALESCE(?, device_type),
+            manufacturer = CASE WHEN ? THEN ? ELSE manufacturer END,
+            model = CASE WHEN ? THEN ? ELSE model END,
+            location = CASE WHEN ? THEN ? ELSE location END,
+            household_id = COALESCE(?, household_id)
+        WHERE id = ?
+        RETURNING
+            id as "id!", name as "name!", device_type as "device_type!: DeviceType",
+            manufacturer, model, location,
+            effect as "effect!", account_id as "account_id!", household_id as "household_id!"
         "#,
-        create_device_request.effect,
-        account_id
+        effect,
+        name,
+        device_type,
+        set_manufacturer,
+        manufacturer,
+        set_model,
+        model,
+        set_room,
+        room,
+        household_id,
+        id
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(internal_error)?;
+    .fetch_optional(&mut *tx)
+    .await?
+    // Deleted since the role was checked
+    .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
 
-    let device = Device {
+    // Exclusion groups only span the devices of one household
+    sqlx::query!(
+        r#"
+        DELETE FROM ExclusionGroupDevices
+        WHERE device_id = ? AND group_id IN (
+            SELECT id FROM ExclusionGroups WHERE household_id != ?
+        )
+        "#,
         id,
-        effect: create_device_request.effect,
-        account_id,
-    };
+        device.household_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
 
-    Ok(Json(device))
+    Ok(VersionedJson(version, device.into()))
 }
 
-#[debug_handler]
-pub async fn delete_smart_device(
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/availability",
+    tag = "devices",
+    params(("id" = i64, Path, description = "Id of the device")),
+    responses((status = 200, description = "When the device may run", body = DeviceAvailability)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_device_availability(
     State(pool): State<SqlitePool>,
-    Authentication(account_id): Authentication,
-    Json(device): Json<Device>,
-) -> Result<(), (StatusCode, String)> {
+    Authentication(account_id, access): Authentication,
+    Path(id): Path<i64>,
+) -> Result<Json<DeviceAvailability>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    device_role(account_id, id, &pool)
+        .await?
+        .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
+
+    let availability = device_availability(id, &mut *pool.acquire().await?).await?;
+
+    Ok(Json(availability))
+}
+
+#[utoipa::path(
+    put,
+    path = "/devices/{id}/availability",
+    tag = "devices",
+    params(("id" = i64, Path, description = "Id of the device")),
+    request_body = DeviceAvailability,
+    responses((status = 200, description = "The new availability", body = DeviceAvailability)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn put_device_availability(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    Path(id): Path<i64>,
+    Json(availability): Json<DeviceAvailability>,
+) -> Result<Json<DeviceAvailability>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let role = device_role(account_id, id, &pool)
+        .await?
+        .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
+    require_role(role, Role::Member)?;
+
+    if availability.blackouts.iter().any(|b| b.start >= b.end) {
+        return Err(AppError::invalid(
+            "blackouts",
+            "Blackouts must start before they end",
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM DeviceAllowedHours
+        WHERE device_id = ?
+        "#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
     sqlx::query!(
+        r#"
+        DELETE FROM DeviceBlackouts
+        WHERE device_id = ?
+        "#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for hours in &availability.allowed_hours {
+        sqlx::query!(
+            r#"
+            INSERT INTO DeviceAllowedHours (device_id, start_time, end_time)
+            VALUES (?, ?, ?)
+            "#,
+            id,
+            hours.start,
+            hours.end
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for blackout in &availability.blackouts {
+        sqlx::query!(
+            r#"
+            INSERT INTO DeviceBlackouts (device_id, timespan_start, timespan_end)
+            VALUES (?, ?, ?)
+            "#,
+            id,
+            blackout.start,
+            blackout.end
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    scheduler.trigger();
+
+    Ok(Json(availability))
+}
+
+#[utoipa::path(
+    post,
+    path = "/device/delete",
+    tag = "devices",
+    request_body = DeleteDeviceRequest,
+    responses((status = 200, description = "The device was deleted")),
+)]
+#[deprecated = "Superseded by the resource routes"]
+#[debug_handler(state = AppState)]
+pub async fn delete_smart_device(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Json(delete_device_request): Json<DeleteDeviceRequest>,
+) -> Result<(), AppError> {
+    remove_device(account_id, &access, delete_device_request.id, &pool).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/devices/{id}",
+    tag = "devices",
+    params(("id" = i64, Path, description = "Id of the device")),
+    responses((status = 204, description = "The device was deleted")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn delete_smart_device_by_id(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    remove_device(account_id, &access, id, &pool).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn insert_device(
+    account_id: i64,
+    access: &Access,
+    create_device_request: CreateDeviceRequest,
+    pool: &SqlitePool,
+) -> Result<Device, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let household_id = match create_device_request.household_id {
+        Some(household_id) => household_id,
+        None => personal_household(account_id, pool).await?,
+    };
+
+    let role = household_role(account_id, household_id, pool)
+        .await?
+        .ok_or(AppError::NotFound(
+            "No household with id exists".to_string(),
+        ))?;
+    require_role(role, Role::Member)?;
+
+    let name = create_device_request
+        .name
+        .unwrap_or_else(|| create_device_request.device_type.label().to_string());
+
+    let device = sqlx::query_as!(
+        DeviceRow,
+        r#"
+        INSERT INTO Devices (effect, name, device_type, manufacturer, model, location, account_id, household_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING
+            id, name, device_type as "device_type: DeviceType",
+            manufacturer, model, location,
+            effect, account_id, household_id as "household_id!"
+        "#,
+        create_device_request.effect,
+        name,
+        create_device_request.device_type,
+        create_device_request.manufacturer,
+        create_device_request.model,
+        create_device_request.room,
+        account_id,
+        household_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(device.into())
+}
+
+async fn remove_device(
+    account_id: i64,
+    access: &Access,
+    id: i64,
+    pool: &SqlitePool,
+) -> Result<(), AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let role = device_role(account_id, id, pool)
+        .await?
+        .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
+    require_role(role, Role::Member)?;
+
+    let result = sqlx::query!(
         r#"
         DELETE FROM Devices
-        WHERE id == ? AND account_id == ?
+        WHERE id == ?
         "#,
-        device.id,
-        account_id
+        id
     )
-    .execute(&pool)
-    .await
-    .map_err(internal_error)?;
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("No device with id exists".to_string()));
+    }
 
     Ok(())
 }
+
+/// When the device may run, see [`DeviceAvailability`].
+pub async fn device_availability(
+    device_id: i64,
+    conn: &mut SqliteConnection,
+) -> Result<DeviceAvailability, sqlx::Error> {
+    let allowed_hours = sqlx::query_as!(
+        AllowedHours,
+        r#"
+        SELECT start_time as "start: NaiveTime", end_time as "end: NaiveTime"
+        FROM DeviceAllowedHours
+        WHERE device_id = ?
+        ORDER BY start_time
+        "#,
+        device_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let blackouts = sqlx::query!(
+        r#"
+        SELECT timespan_start, timespan_end
+        FROM DeviceBlackouts
+        WHERE device_id = ?
+        ORDER BY timespan_start
+        "#,
+        device_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(DeviceAvailability {
+        allowed_hours,
+        blackouts: blackouts
+            .into_iter()
+            .map(|b| Timespan::new_from_naive(b.timespan_start, b.timespan_end))
+            .collect(),
+    })
+}
+
+// The household created alongside the account, used when a request does not name one
+pub async fn personal_household(account_id: i64, pool: &SqlitePool) -> Result<i64, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT household_id
+        FROM HouseholdMembers
+        WHERE account_id = ? AND role = 'owner'
+        ORDER BY household_id
+        LIMIT 1
+        "#,
+        account_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::BadRequest(
+        "Account owns no household, a household_id is required".to_string(),
+    ))
+}
+
+struct DeviceRow {
+    id: i64,
+    name: String,
+    device_type: DeviceType,
+    manufacturer: Option<String>,
+    model: Option<String>,
+    location: Option<String>,
+    effect: f64,
+    account_id: i64,
+    household_id: i64,
+}
+
+impl From<DeviceRow> for Device {
+    fn from(row: DeviceRow) -> Self {
+        Device {
+            id: row.id,
+            name: row.name,
+            device_type: row.device_type,
+            manufacturer: row.manufacturer,
+            model: row.model,
+            room: row.location,
+            effect: row.effect,
+            account_id: row.account_id,
+            household_id: row.household_id,
+            defaults: row.device_type.defaults(),
+        }
+    }
+}
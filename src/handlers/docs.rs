@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use axum::{
+    debug_handler,
+    http::header,
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use utoipa::OpenApi;
+
+use crate::{error::AppError, extractors::path::Path, openapi::ApiDoc};
+
+// Swagger UI is built into the binary, so nothing has to be loaded from elsewhere
+const DOCS_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; \
+    script-src 'self'; \
+    style-src 'self'; \
+    img-src 'self' data:; \
+    connect-src 'self'; \
+    frame-ancestors 'none'";
+
+#[debug_handler]
+pub async fn get_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+// Swagger UI refers to its files relative to the page, which only works below `/docs/`
+#[debug_handler]
+pub async fn get_docs() -> Redirect {
+    Redirect::permanent("/docs/")
+}
+
+#[debug_handler]
+pub async fn get_docs_index() -> Result<Response, AppError> {
+    docs_file("index.html")
+}
+
+#[debug_handler]
+pub async fn get_docs_file(Path(file): Path<String>) -> Result<Response, AppError> {
+    docs_file(&file)
+}
+
+fn docs_file(file: &str) -> Result<Response, AppError> {
+    let config = Arc::new(utoipa_swagger_ui::Config::from("/openapi.json"));
+
+    let file = utoipa_swagger_ui::serve(file, config)
+        .map_err(|e| AppError::Internal(e.to_string().into()))?
+        .ok_or(AppError::NotFound("No such documentation file".to_string()))?;
+
+    Ok((
+        [(header::CONTENT_TYPE, file.content_type)],
+        [(
+            header::CONTENT_SECURITY_POLICY,
+            DOCS_CONTENT_SECURITY_POLICY,
+        )],
+        file.bytes.into_owned(),
+    )
+        .into_response())
+}
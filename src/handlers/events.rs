@@ -0,0 +1,49 @@
+use axum::{debug_handler, extract::State};
+use chrono::{TimeZone, Utc};
+use sqlx::SqlitePool;
+
+use crate::{
+    data_model::{api_key::Scope, event::Event},
+    error::AppError,
+    extractors::{auth::Authentication, json::Json},
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/events/all",
+    tag = "events",
+    responses((status = 200, description = "When scheduled tasks will start", body = [Event])),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_events(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+) -> Result<Json<Vec<Event>>, AppError> {
+    access.require(Scope::ReadReports)?;
+
+    let events = sqlx::query!(
+        r#"
+        SELECT Events.id, Events.task_id, Events.start_time
+        FROM Events
+        JOIN Tasks ON Events.task_id == Tasks.id
+        JOIN Devices ON Tasks.device_id == Devices.id
+        JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ?
+        "#,
+        account_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(
+        events
+            .iter()
+            .map(|e| Event {
+                id: e.id,
+                task_id: e.task_id,
+                start_time: Utc.from_utc_datetime(&e.start_time),
+            })
+            .collect(),
+    ))
+}
@@ -0,0 +1,431 @@
+use axum::{
+    debug_handler,
+    extract::{OriginalUri, State},
+    http::StatusCode,
+};
+use chrono::{Duration, TimeZone, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::{
+    data_model::{
+        api_key::Scope,
+        exclusion_group::ExclusionGroup,
+        household::Role,
+        time::{Milliseconds, Timespan},
+    },
+    error::AppError,
+    extractors::{auth::Authentication, json::Json, path::Path},
+    handlers::{
+        devices::personal_household,
+        util::{created, Created},
+    },
+    protocol::exclusion_groups::{
+        Conflict, ConflictCheckRequest, CreateExclusionGroupRequest, UpdateExclusionGroupRequest,
+    },
+    scheduling::SchedulerHandle,
+    security::authorization::{device_role, exclusion_group_role, household_role, require_role},
+    state::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/exclusion-groups",
+    tag = "exclusion groups",
+    responses((status = 200, description = "The exclusion groups of the account's households", body = [ExclusionGroup])),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_exclusion_groups(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+) -> Result<Json<Vec<ExclusionGroup>>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT ExclusionGroups.id
+        FROM ExclusionGroups
+        JOIN HouseholdMembers ON ExclusionGroups.household_id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ?
+        ORDER BY ExclusionGroups.id
+        "#,
+        account_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut groups = Vec::with_capacity(ids.len());
+    for id in ids {
+        groups.push(exclusion_group(id, &pool).await?);
+    }
+
+    Ok(Json(groups))
+}
+
+#[utoipa::path(
+    get,
+    path = "/exclusion-groups/{id}",
+    tag = "exclusion groups",
+    params(("id" = i64, Path, description = "Id of the exclusion group")),
+    responses((status = 200, description = "The exclusion group", body = ExclusionGroup)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_exclusion_group(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Path(id): Path<i64>,
+) -> Result<Json<ExclusionGroup>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    exclusion_group_role(account_id, id, &pool)
+        .await?
+        .ok_or(AppError::NotFound(
+            "No exclusion group with id exists".to_string(),
+        ))?;
+
+    let group = exclusion_group(id, &pool).await?;
+
+    Ok(Json(group))
+}
+
+#[utoipa::path(
+    post,
+    path = "/exclusion-groups",
+    tag = "exclusion groups",
+    request_body = CreateExclusionGroupRequest,
+    responses((status = 201, description = "The created group", body = ExclusionGroup, headers(("Location" = String, description = "Where the created group can be found")))),
+)]
+#[debug_handler(state = AppState)]
+pub async fn post_exclusion_group(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    OriginalUri(uri): OriginalUri,
+    Json(create_group_request): Json<CreateExclusionGroupRequest>,
+) -> Result<Created<Json<ExclusionGroup>>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let household_id = match create_group_request.household_id {
+        Some(household_id) => household_id,
+        None => personal_household(account_id, &pool).await?,
+    };
+
+    let role = household_role(account_id, household_id, &pool)
+        .await?
+        .ok_or(AppError::NotFound(
+            "No household with id exists".to_string(),
+        ))?;
+    require_role(role, Role::Member)?;
+
+    validate_max_concurrent(create_group_request.max_concurrent)?;
+
+    let mut tx = pool.begin().await?;
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO ExclusionGroups (household_id, name, max_concurrent)
+        VALUES (?, ?, ?)
+        RETURNING id
+        "#,
+        household_id,
+        create_group_request.name,
+        create_group_request.max_concurrent
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    set_members(id, household_id, &create_group_request.device_ids, &mut tx).await?;
+
+    tx.commit().await?;
+
+    scheduler.trigger();
+
+    let group = exclusion_group(id, &pool).await?;
+
+    Ok(created(&uri, id, Json(group)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/exclusion-groups/{id}",
+    tag = "exclusion groups",
+    params(("id" = i64, Path, description = "Id of the exclusion group")),
+    request_body = UpdateExclusionGroupRequest,
+    responses((status = 200, description = "The updated group", body = ExclusionGroup)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn update_exclusion_group(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    Path(id): Path<i64>,
+    Json(update_group_request): Json<UpdateExclusionGroupRequest>,
+) -> Result<Json<ExclusionGroup>, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let role = exclusion_group_role(account_id, id, &pool)
+        .await?
+        .ok_or(AppError::NotFound(
+            "No exclusion group with id exists".to_string(),
+        ))?;
+    require_role(role, Role::Member)?;
+
+    if let Some(max_concurrent) = update_group_request.max_concurrent {
+        validate_max_concurrent(max_concurrent)?;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let household_id = sqlx::query_scalar!(
+        r#"
+        UPDATE ExclusionGroups
+        SET
+            name = COALESCE(?, name),
+            max_concurrent = COALESCE(?, max_concurrent)
+        WHERE id = ?
+        RETURNING household_id as "household_id!"
+        "#,
+        update_group_request.name,
+        update_group_request.max_concurrent,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(device_ids) = &update_group_request.device_ids {
+        set_members(id, household_id, device_ids, &mut tx).await?;
+    }
+
+    tx.commit().await?;
+
+    scheduler.trigger();
+
+    let group = exclusion_group(id, &pool).await?;
+
+    Ok(Json(group))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/exclusion-groups/{id}",
+    tag = "exclusion groups",
+    params(("id" = i64, Path, description = "Id of the exclusion group")),
+    responses((status = 204, description = "The group was deleted")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn delete_exclusion_group_by_id(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    access.require(Scope::ManageDevices)?;
+
+    let role = exclusion_group_role(account_id, id, &pool)
+        .await?
+        .ok_or(AppError::NotFound(
+            "No exclusion group with id exists".to_string(),
+        ))?;
+    require_role(role, Role::Member)?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM ExclusionGroups
+        WHERE id = ?
+        "#,
+        id
+    )
+    .execute(&pool)
+    .await?;
+
+    scheduler.trigger();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists the exclusion groups a run of a device would push over their limit, given
+/// the events currently scheduled. An empty list means the run is free of conflicts.
+#[utoipa::path(
+    post,
+    path = "/conflicts/check",
+    tag = "exclusion groups",
+    request_body = ConflictCheckRequest,
+    responses((status = 200, description = "The groups the run would push over their limit", body = [Conflict])),
+)]
+#[debug_handler(state = AppState)]
+pub async fn check_conflicts(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Json(conflict_check_request): Json<ConflictCheckRequest>,
+) -> Result<Json<Vec<Conflict>>, AppError> {
+    access.require(Scope::ReadReports)?;
+
+    let device_id = conflict_check_request.device_id;
+    let run = conflict_check_request.run;
+
+    device_role(account_id, device_id, &pool)
+        .await?
+        .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
+
+    if run.start >= run.end {
+        return Err(AppError::invalid("run", "Run must start before it ends"));
+    }
+
+    let group_ids = sqlx::query_scalar!(
+        r#"
+        SELECT group_id
+        FROM ExclusionGroupDevices
+        WHERE device_id = ?
+        ORDER BY group_id
+        "#,
+        device_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut conflicts = Vec::new();
+    for group_id in group_ids {
+        let group = exclusion_group(group_id, &pool).await?;
+
+        let scheduled = sqlx::query!(
+            r#"
+            SELECT Events.task_id, Events.start_time, Tasks.duration
+            FROM Events
+            JOIN Tasks ON Events.task_id == Tasks.id
+            JOIN ExclusionGroupDevices ON Tasks.device_id == ExclusionGroupDevices.device_id
+            WHERE ExclusionGroupDevices.group_id = ?
+            "#,
+            group_id
+        )
+        .fetch_all(&pool)
+        .await?;
+
+        let scheduled: Vec<(i64, Timespan)> = scheduled
+            .into_iter()
+            .map(|e| {
+                let start = Utc.from_utc_datetime(&e.start_time);
+                (
+                    e.task_id,
+                    Timespan::new(
+                        start,
+                        start + Duration::from(Milliseconds::from(e.duration)),
+                    ),
+                )
+            })
+            .collect();
+
+        let runs: Vec<Timespan> = scheduled.iter().map(|(_, run)| *run).collect();
+        if group.admits(&runs, &run) {
+            continue;
+        }
+
+        conflicts.push(Conflict {
+            group_id,
+            name: group.name,
+            max_concurrent: group.max_concurrent,
+            task_ids: scheduled
+                .into_iter()
+                .filter(|(_, scheduled_run)| scheduled_run.overlaps(&run))
+                .map(|(task_id, _)| task_id)
+                .collect(),
+        });
+    }
+
+    Ok(Json(conflicts))
+}
+
+/// The exclusion group `id` with its member devices, which must exist.
+async fn exclusion_group(id: i64, pool: &SqlitePool) -> Result<ExclusionGroup, sqlx::Error> {
+    let group = sqlx::query!(
+        r#"
+        SELECT id, household_id, name, max_concurrent
+        FROM ExclusionGroups
+        WHERE id = ?
+        "#,
+        id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let device_ids = sqlx::query_scalar!(
+        r#"
+        SELECT device_id
+        FROM ExclusionGroupDevices
+        WHERE group_id = ?
+        ORDER BY device_id
+        "#,
+        id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ExclusionGroup {
+        id: group.id,
+        household_id: group.household_id,
+        name: group.name,
+        max_concurrent: group.max_concurrent,
+        device_ids,
+    })
+}
+
+// Replaces the members of a group, which may only be devices of its own household
+async fn set_members(
+    group_id: i64,
+    household_id: i64,
+    device_ids: &[i64],
+    tx: &mut SqliteConnection,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        DELETE FROM ExclusionGroupDevices
+        WHERE group_id = ?
+        "#,
+        group_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for device_id in device_ids {
+        let device_household_id = sqlx::query_scalar!(
+            r#"
+            SELECT household_id
+            FROM Devices
+            WHERE id = ?
+            "#,
+            device_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .flatten();
+
+        if device_household_id != Some(household_id) {
+            return Err(AppError::invalid(
+                "device_ids",
+                format!("Device {device_id} does not belong to the group's household"),
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO ExclusionGroupDevices (group_id, device_id)
+            VALUES (?, ?)
+            "#,
+            group_id,
+            device_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn validate_max_concurrent(max_concurrent: i64) -> Result<(), AppError> {
+    if max_concurrent < 1 {
+        return Err(AppError::invalid(
+            "max_concurrent",
+            "At least one device of a group must be allowed to run",
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use axum::{debug_handler, extract::State, http::StatusCode, Json};
+use sqlx::{migrate::Migrate, SqlitePool};
+
+use crate::{
+    health::Health,
+    protocol::health::{ReadinessChecks, ReadinessResponse},
+    scheduling::SchedulerHandle,
+    state::AppState,
+    MIGRATOR,
+};
+
+/// Answers as long as the process is able to serve requests at all.
+#[debug_handler]
+pub async fn get_healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Whether traffic should be sent here, 503 when any of the checks fails.
+#[debug_handler(state = AppState)]
+pub async fn get_readyz(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    State(health): State<Health>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let database = sqlx::query("SELECT 1").execute(&pool).await.is_ok();
+
+    let checks = ReadinessChecks {
+        database,
+        migrations: database && migrations_applied(&pool).await,
+        scheduler: scheduler.is_running(),
+        shutting_down: health.is_shutting_down(),
+    };
+
+    let ready = checks.database && checks.migrations && checks.scheduler && !checks.shutting_down;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(ReadinessResponse { ready, checks }))
+}
+
+async fn migrations_applied(pool: &SqlitePool) -> bool {
+    let Ok(mut connection) = pool.acquire().await else {
+        return false;
+    };
+    let Ok(applied) = connection.list_applied_migrations().await else {
+        return false;
+    };
+
+    MIGRATOR
+        .iter()
+        .all(|migration| applied.iter().any(|a| a.version == migration.version))
+}
@@ -0,0 +1,308 @@
+use axum::{debug_handler, extract::State};
+use chrono::{Days, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    data_model::household::{Household, Role},
+    error::AppError,
+    extractors::{auth::Authentication, json::Json},
+    protocol::households::{
+        CreateHouseholdRequest, CreateInvitationRequest, HouseholdMembership, Invitation,
+        JoinHouseholdRequest, LeaveHouseholdRequest,
+    },
+    security::authorization::{household_role, require_role},
+    state::AppState,
+};
+
+const INVITATION_LIFETIME_DAYS: u64 = 7;
+
+#[utoipa::path(
+    get,
+    path = "/households/all",
+    tag = "households",
+    responses((status = 200, description = "The households the account is a member of", body = [HouseholdMembership])),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_households(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+) -> Result<Json<Vec<HouseholdMembership>>, AppError> {
+    access.require_session()?;
+
+    let households = sqlx::query!(
+        r#"
+        SELECT Households.id, Households.name, HouseholdMembers.role as "role: Role"
+        FROM Households
+        JOIN HouseholdMembers ON Households.id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ?
+        "#,
+        account_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(
+        households
+            .into_iter()
+            .map(|h| HouseholdMembership {
+                household: Household {
+                    id: h.id,
+                    name: h.name,
+                },
+                role: h.role,
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/households/create",
+    tag = "households",
+    request_body = CreateHouseholdRequest,
+    responses((status = 200, description = "The created household, owned by the account", body = HouseholdMembership)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn create_household(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Json(create_household_request): Json<CreateHouseholdRequest>,
+) -> Result<Json<HouseholdMembership>, AppError> {
+    access.require_session()?;
+
+    let mut tx = pool.begin().await?;
+
+    let household_id =
+        insert_household(account_id, &create_household_request.name, &mut tx).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(HouseholdMembership {
+        household: Household {
+            id: household_id,
+            name: create_household_request.name,
+        },
+        role: Role::Owner,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/households/invite",
+    tag = "households",
+    request_body = CreateInvitationRequest,
+    responses((status = 200, description = "A single-use invitation code", body = Invitation)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn create_invitation(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Json(create_invitation_request): Json<CreateInvitationRequest>,
+) -> Result<Json<Invitation>, AppError> {
+    access.require_session()?;
+
+    let household_id = create_invitation_request.household_id;
+
+    let role = household_role(account_id, household_id, &pool)
+        .await?
+        .ok_or(AppError::NotFound(
+            "No household with id exists".to_string(),
+        ))?;
+    require_role(role, Role::Owner)?;
+
+    let code = Uuid::new_v4().simple().to_string();
+    let expires_at = Utc::now()
+        .checked_add_days(Days::new(INVITATION_LIFETIME_DAYS))
+        .ok_or(AppError::Internal(
+            "Invitation expiry is out of range".into(),
+        ))?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO HouseholdInvitations (code, household_id, role, expires_at)
+        VALUES (?, ?, ?, ?)
+        "#,
+        code,
+        household_id,
+        create_invitation_request.role,
+        expires_at
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(Invitation {
+        code,
+        household_id,
+        role: create_invitation_request.role,
+        expires_at,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/households/join",
+    tag = "households",
+    request_body = JoinHouseholdRequest,
+    responses((status = 200, description = "The joined household", body = HouseholdMembership)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn join_household(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Json(join_household_request): Json<JoinHouseholdRequest>,
+) -> Result<Json<HouseholdMembership>, AppError> {
+    access.require_session()?;
+
+    let mut tx = pool.begin().await?;
+
+    // Invitations are single use
+    let invitation = sqlx::query!(
+        r#"
+        DELETE FROM HouseholdInvitations
+        WHERE code = ?
+        RETURNING household_id, role as "role: Role", expires_at
+        "#,
+        join_household_request.code
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let invitation = match invitation {
+        Some(invitation) if invitation.expires_at > Utc::now().naive_utc() => invitation,
+        _ => {
+            return Err(AppError::NotFound(
+                "Invitation code is invalid or has expired".to_string(),
+            ))
+        }
+    };
+
+    // Joining a household one is already part of keeps the better of the two roles
+    sqlx::query!(
+        r#"
+        INSERT INTO HouseholdMembers (household_id, account_id, role)
+        VALUES (?, ?, ?)
+        ON CONFLICT(household_id, account_id) DO UPDATE SET role = CASE
+            WHEN role = 'owner' OR excluded.role = 'owner' THEN 'owner'
+            WHEN role = 'member' OR excluded.role = 'member' THEN 'member'
+            ELSE 'viewer'
+        END
+        "#,
+        invitation.household_id,
+        account_id,
+        invitation.role
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let membership = sqlx::query!(
+        r#"
+        SELECT Households.id, Households.name, HouseholdMembers.role as "role: Role"
+        FROM Households
+        JOIN HouseholdMembers ON Households.id == HouseholdMembers.household_id
+        WHERE Households.id = ? AND HouseholdMembers.account_id = ?
+        "#,
+        invitation.household_id,
+        account_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(HouseholdMembership {
+        household: Household {
+            id: membership.id,
+            name: membership.name,
+        },
+        role: membership.role,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/households/leave",
+    tag = "households",
+    request_body = LeaveHouseholdRequest,
+    responses((status = 200, description = "The account left the household")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn leave_household(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    Json(leave_household_request): Json<LeaveHouseholdRequest>,
+) -> Result<(), AppError> {
+    access.require_session()?;
+
+    let household_id = leave_household_request.household_id;
+
+    let role = household_role(account_id, household_id, &pool)
+        .await?
+        .ok_or(AppError::NotFound(
+            "No household with id exists".to_string(),
+        ))?;
+
+    // Checked in the same statement as the delete, so two owners leaving at once cannot
+    // both see the other one still there
+    let left = sqlx::query!(
+        r#"
+        DELETE FROM HouseholdMembers
+        WHERE household_id = ? AND account_id = ? AND (
+            role != 'owner'
+            OR (
+                SELECT COUNT(*)
+                FROM HouseholdMembers
+                WHERE household_id = ? AND role = 'owner'
+            ) > 1
+        )
+        "#,
+        household_id,
+        account_id,
+        household_id
+    )
+    .execute(&pool)
+    .await?
+    .rows_affected();
+
+    if left == 0 {
+        return Err(match role {
+            Role::Owner => {
+                AppError::Conflict("The last owner cannot leave a household".to_string())
+            }
+            _ => AppError::NotFound("No household with id exists".to_string()),
+        });
+    }
+
+    Ok(())
+}
+
+/// Creates a household with `account_id` as its owner.
+pub async fn insert_household(
+    account_id: i64,
+    name: &str,
+    tx: &mut sqlx::SqliteConnection,
+) -> Result<i64, AppError> {
+    let household_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO Households (name)
+        VALUES (?)
+        RETURNING id
+        "#,
+        name
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO HouseholdMembers (household_id, account_id, role)
+        VALUES (?, ?, 'owner')
+        "#,
+        household_id,
+        account_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(household_id)
+}
@@ -0,0 +1,14 @@
+use axum::{debug_handler, extract::State, http::header, response::IntoResponse};
+use sqlx::SqlitePool;
+
+use crate::{error::AppError, metrics::Metrics, state::AppState};
+
+#[debug_handler(state = AppState)]
+pub async fn get_metrics(
+    State(pool): State<SqlitePool>,
+    State(metrics): State<Metrics>,
+) -> Result<impl IntoResponse, AppError> {
+    let body = metrics.render(&pool).await.map_err(AppError::Internal)?;
+
+    Ok(([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body))
+}
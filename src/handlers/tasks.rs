@@ -1,100 +1,795 @@
-use axum::{debug_handler, extract::State, http::StatusCode, Json};
-use sqlx::SqlitePool;
+use axum::{
+    debug_handler,
+    extract::{OriginalUri, State},
+    http::StatusCode,
+};
+use chrono::{Days, Utc};
+use sqlx::{types::Json as SqlJson, Sqlite, SqliteConnection, SqlitePool};
 
 use crate::{
-    data_model::{task::Task, time::Timespan},
-    extractors::auth::Authentication,
-    handlers::util::internal_error,
-    protocol::tasks::CreateTaskRequest,
+    data_model::{
+        api_key::Scope,
+        household::Role,
+        task::Task,
+        time::{Milliseconds, Timespan},
+    },
+    error::AppError,
+    extractors::{
+        auth::{Access, Authentication},
+        json::{Json, VersionedBody},
+        path::Path,
+        query::Query,
+    },
+    handlers::{
+        devices::device_availability,
+        util::{created, page, page_size, stale_cursor, Created, Page, VersionedJson},
+    },
+    protocol::{
+        tasks::{
+            CreateTaskRequest, CreateTasksBatchRequest, DeleteTaskRequest, DeleteTasksBatchRequest,
+            TaskQuery, UpdateTaskRequest,
+        },
+        versioning::ApiVersion,
+    },
+    scheduling::SchedulerHandle,
+    security::authorization::{device_role, require_role, task_role},
+    state::AppState,
 };
 
-#[debug_handler]
+const MAX_TAG_LENGTH: usize = 64;
+const MAX_BATCH_SIZE: usize = 500;
+const MAX_TIMESPAN_DAYS: u64 = 366;
+
+#[utoipa::path(
+    get,
+    path = "/tasks",
+    tag = "tasks",
+    params(TaskQuery),
+    responses((status = 200, description = "A page of tasks, with a `Link` header to the next one if there is more", body = [Task])),
+)]
+#[debug_handler(state = AppState)]
 pub async fn get_tasks(
     State(pool): State<SqlitePool>,
-    Authentication(account_id): Authentication,
-) -> Result<Json<Vec<Task>>, (StatusCode, String)> {
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<TaskQuery>,
+) -> Result<Page<Task>, AppError> {
+    access.require(Scope::ReadTasks)?;
+
+    let page_size = page_size(query.limit)?;
+    // One more than fits the page, to tell whether there is a next one
+    let fetch_limit = page_size + 1;
+    let now = Utc::now();
+
+    // Read together with the page, so the cursor cannot disappear in between
+    let mut tx = pool.begin().await?;
+
+    // Cursors pointing at someone else's items are treated like missing ones
+    if let Some(cursor) = query.cursor {
+        let exists = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM Tasks
+                JOIN Devices ON Tasks.device_id == Devices.id
+                JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+                WHERE Tasks.id = ? AND HouseholdMembers.account_id = ?
+            ) as "exists!: bool"
+            "#,
+            cursor,
+            account_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if !exists {
+            return Err(stale_cursor());
+        }
+    }
+
     let tasks = sqlx::query!(
         r#"
-        SELECT Tasks.id, Tasks.timespan_start, Tasks.timespan_end, Tasks.duration, Tasks.device_id
+        SELECT
+            Tasks.id, Tasks.timespan_start, Tasks.timespan_end, Tasks.duration, Tasks.device_id,
+            Tasks.title, Tasks.notes,
+            (
+                SELECT json_group_array(tag)
+                FROM (SELECT tag FROM TaskTags WHERE task_id = Tasks.id ORDER BY tag)
+            ) as "tags!: SqlJson<Vec<String>>"
         FROM Tasks
         JOIN Devices ON Tasks.device_id == Devices.id
-        WHERE Devices.account_id = ?
+        JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+        LEFT JOIN Events ON Events.task_id == Tasks.id
+        WHERE HouseholdMembers.account_id = ?1
+            AND (?2 IS NULL OR Tasks.device_id = ?2)
+            AND (?3 IS NULL OR Tasks.timespan_end > ?3)
+            AND (?4 IS NULL OR Tasks.timespan_start < ?4)
+            AND (?5 IS NULL OR ?5 = CASE
+                WHEN Events.id IS NULL AND julianday(Tasks.timespan_end) <= julianday(?6) THEN 'missed'
+                WHEN Events.id IS NULL THEN 'pending'
+                WHEN julianday(Events.start_time) > julianday(?6) THEN 'scheduled'
+                WHEN julianday(Events.start_time) + Tasks.duration / 86400000.0 > julianday(?6) THEN 'running'
+                ELSE 'completed'
+            END)
+            AND (?11 IS NULL OR EXISTS (
+                SELECT 1 FROM TaskTags WHERE TaskTags.task_id = Tasks.id AND TaskTags.tag = ?11
+            ))
+            AND (?9 IS NULL OR CASE ?8
+                WHEN 'asc' THEN
+                    (CASE ?7 WHEN 'start' THEN Tasks.timespan_start WHEN 'end' THEN Tasks.timespan_end ELSE Tasks.id END, Tasks.id)
+                    > (SELECT CASE ?7 WHEN 'start' THEN timespan_start WHEN 'end' THEN timespan_end ELSE id END, id FROM Tasks WHERE id = ?9)
+                ELSE
+                    (CASE ?7 WHEN 'start' THEN Tasks.timespan_start WHEN 'end' THEN Tasks.timespan_end ELSE Tasks.id END, Tasks.id)
+                    < (SELECT CASE ?7 WHEN 'start' THEN timespan_start WHEN 'end' THEN timespan_end ELSE id END, id FROM Tasks WHERE id = ?9)
+            END)
+        ORDER BY
+            CASE WHEN ?8 = 'asc' THEN CASE ?7 WHEN 'start' THEN Tasks.timespan_start WHEN 'end' THEN Tasks.timespan_end ELSE Tasks.id END END ASC,
+            CASE WHEN ?8 = 'desc' THEN CASE ?7 WHEN 'start' THEN Tasks.timespan_start WHEN 'end' THEN Tasks.timespan_end ELSE Tasks.id END END DESC,
+            CASE WHEN ?8 = 'asc' THEN Tasks.id END ASC,
+            CASE WHEN ?8 = 'desc' THEN Tasks.id END DESC
+        LIMIT ?10
         "#,
-        account_id
+        account_id,
+        query.device_id,
+        query.start,
+        query.end,
+        query.status,
+        now,
+        query.sort,
+        query.order,
+        query.cursor,
+        fetch_limit,
+        query.tag
     )
-    .fetch_all(&pool)
-    .await
-    .map_err(internal_error)?;
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
 
     let my_tasks = tasks
-        .iter()
+        .into_iter()
         .map(|t| Task {
             id: t.id,
             timespan: Timespan::new_from_naive(t.timespan_start, t.timespan_end),
             duration: t.duration.into(),
             device_id: t.device_id,
+            title: t.title,
+            notes: t.notes,
+            tags: t.tags.0,
         })
         .collect();
 
-    Ok(Json(my_tasks))
+    Ok(page(version, &uri, my_tasks, page_size, |last| TaskQuery {
+        cursor: Some(last.id),
+        ..query.clone()
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/all",
+    tag = "tasks",
+    params(TaskQuery),
+    responses((status = 200, description = "A page of tasks, with a `Link` header to the next one if there is more", body = [Task])),
+)]
+#[deprecated = "Superseded by the resource routes"]
+#[debug_handler(state = AppState)]
+pub async fn get_all_tasks(
+    state: State<SqlitePool>,
+    authentication: Authentication,
+    version: ApiVersion,
+    uri: OriginalUri,
+    query: Query<TaskQuery>,
+) -> Result<Page<Task>, AppError> {
+    get_tasks(state, authentication, version, uri, query).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = i64, Path, description = "Id of the task")),
+    responses((status = 200, description = "The task", body = Task)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn get_task(
+    State(pool): State<SqlitePool>,
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    Path(id): Path<i64>,
+) -> Result<VersionedJson<Task>, AppError> {
+    access.require(Scope::ReadTasks)?;
+
+    let task = sqlx::query!(
+        r#"
+        SELECT
+            Tasks.id, Tasks.timespan_start, Tasks.timespan_end, Tasks.duration, Tasks.device_id,
+            Tasks.title, Tasks.notes
+        FROM Tasks
+        JOIN Devices ON Tasks.device_id == Devices.id
+        JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ? AND Tasks.id = ?
+        "#,
+        account_id,
+        id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound("No task with id exists".to_string()))?;
+
+    let tags = task_tags(id, &pool).await?;
+
+    Ok(VersionedJson(
+        version,
+        Task {
+            id: task.id,
+            timespan: Timespan::new_from_naive(task.timespan_start, task.timespan_end),
+            duration: task.duration.into(),
+            device_id: task.device_id,
+            title: task.title,
+            notes: task.notes,
+            tags,
+        },
+    ))
 }
 
-#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/tasks/create",
+    tag = "tasks",
+    request_body = CreateTaskRequest,
+    responses((status = 200, description = "The created task", body = Task)),
+)]
+#[deprecated = "Superseded by the resource routes"]
+#[debug_handler(state = AppState)]
 pub async fn create_task(
     State(pool): State<SqlitePool>,
-    Authentication(account_id): Authentication,
-    Json(create_task_request): Json<CreateTaskRequest>,
-) -> Result<Json<Task>, (StatusCode, String)> {
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    VersionedBody(create_task_request): VersionedBody<CreateTaskRequest>,
+) -> Result<VersionedJson<Task>, AppError> {
+    let task = insert_task(account_id, &access, create_task_request, &pool).await?;
+    scheduler.trigger();
+
+    Ok(VersionedJson(version, task))
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks",
+    tag = "tasks",
+    request_body = CreateTaskRequest,
+    responses((status = 201, description = "The created task", body = Task, headers(("Location" = String, description = "Where the created task can be found")))),
+)]
+#[debug_handler(state = AppState)]
+pub async fn post_task(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    OriginalUri(uri): OriginalUri,
+    VersionedBody(create_task_request): VersionedBody<CreateTaskRequest>,
+) -> Result<Created<VersionedJson<Task>>, AppError> {
+    let task = insert_task(account_id, &access, create_task_request, &pool).await?;
+    scheduler.trigger();
+
+    Ok(created(&uri, task.id, VersionedJson(version, task)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = i64, Path, description = "Id of the task")),
+    request_body = UpdateTaskRequest,
+    responses((status = 200, description = "The updated task", body = Task)),
+)]
+#[debug_handler(state = AppState)]
+pub async fn update_task(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    Path(id): Path<i64>,
+    VersionedBody(update_task_request): VersionedBody<UpdateTaskRequest>,
+) -> Result<VersionedJson<Task>, AppError> {
+    access.require(Scope::WriteTasks)?;
+
+    // Checked in the transaction that writes, so access cannot change in between
+    let mut tx = pool.begin().await?;
+
+    let role = task_role(account_id, id, &mut *tx)
+        .await?
+        .ok_or(AppError::NotFound("No task with id exists".to_string()))?;
+    require_role(role, Role::Member)?;
+
+    // Moving a task needs the same rights on the new device as creating it there
+    if let Some(device_id) = update_task_request.device_id {
+        let role = device_role(account_id, device_id, &mut *tx)
+            .await?
+            .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
+        require_role(role, Role::Member)?;
+    }
+
+    let current = sqlx::query!(
+        r#"
+        SELECT timespan_start, timespan_end, duration, device_id, title, notes
+        FROM Tasks
+        WHERE id = ?
+        "#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let tags = match update_task_request.tags {
+        Some(tags) => normalize_tags(&tags)?,
+        None => task_tags(id, &mut *tx).await?,
+    };
+
+    let task = Task {
+        id,
+        timespan: update_task_request.timespan.unwrap_or_else(|| {
+            Timespan::new_from_naive(current.timespan_start, current.timespan_end)
+        }),
+        duration: update_task_request
+            .duration
+            .unwrap_or_else(|| current.duration.into()),
+        device_id: update_task_request.device_id.unwrap_or(current.device_id),
+        title: update_task_request.title.unwrap_or(current.title),
+        notes: update_task_request.notes.unwrap_or(current.notes),
+        tags,
+    };
+
+    validate_timing(&task.timespan, task.duration)?;
+    // On the transaction, which already holds the only connection a small pool may have
+    validate_availability(task.device_id, &task.timespan, task.duration, &mut tx).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE Tasks
+        SET timespan_start = ?, timespan_end = ?, duration = ?, device_id = ?, title = ?, notes = ?
+        WHERE id = ?
+        "#,
+        task.timespan.start,
+        task.timespan.end,
+        task.duration,
+        task.device_id,
+        task.title,
+        task.notes,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    set_tags(id, &task.tags, &mut tx).await?;
+
+    // The old start time may no longer fit, so the task waits for the scheduler to place it again
+    sqlx::query!(
+        r#"
+        DELETE FROM Events
+        WHERE task_id = ?
+        "#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    scheduler.trigger();
+
+    Ok(VersionedJson(version, task))
+}
+
+#[utoipa::path(
+    post,
+    path = "/task/delete",
+    tag = "tasks",
+    request_body = DeleteTaskRequest,
+    responses((status = 200, description = "The task was deleted")),
+)]
+#[deprecated = "Superseded by the resource routes"]
+#[debug_handler(state = AppState)]
+pub async fn delete_task(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    Json(delete_task_request): Json<DeleteTaskRequest>,
+) -> Result<(), AppError> {
+    remove_task(account_id, &access, delete_task_request.id, &pool).await?;
+    scheduler.trigger();
+
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = i64, Path, description = "Id of the task")),
+    responses((status = 204, description = "The task was deleted")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn delete_task_by_id(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    remove_task(account_id, &access, id, &pool).await?;
+    scheduler.trigger();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks/batch",
+    tag = "tasks",
+    request_body = CreateTasksBatchRequest,
+    responses((status = 201, description = "The created tasks, in the order they were given", body = [Task])),
+)]
+#[debug_handler(state = AppState)]
+pub async fn create_tasks_batch(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    version: ApiVersion,
+    VersionedBody(batch_request): VersionedBody<CreateTasksBatchRequest>,
+) -> Result<(StatusCode, VersionedJson<Vec<Task>>), AppError> {
+    access.require(Scope::WriteTasks)?;
+    validate_batch_size(batch_request.tasks.len())?;
+
+    // Checked in the transaction that writes, so access cannot change in between
+    let mut tx = pool.begin().await?;
+
+    let mut all_tags = Vec::with_capacity(batch_request.tasks.len());
+    let mut errors = Vec::new();
+    for (index, create_task_request) in batch_request.tasks.iter().enumerate() {
+        match check_task(account_id, create_task_request, &mut tx).await {
+            Ok(tags) => all_tags.push(tags),
+            Err(error @ AppError::Internal(_)) => return Err(error),
+            Err(error) => errors.extend(error.into_field_errors(&format!("tasks[{index}]"))),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    let mut tasks = Vec::with_capacity(batch_request.tasks.len());
+    for (create_task_request, tags) in batch_request.tasks.into_iter().zip(all_tags) {
+        tasks.push(store_task(create_task_request, tags, &mut tx).await?);
+    }
+
+    tx.commit().await?;
+
+    scheduler.trigger();
+
+    Ok((StatusCode::CREATED, VersionedJson(version, tasks)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/tasks/batch/delete",
+    tag = "tasks",
+    request_body = DeleteTasksBatchRequest,
+    responses((status = 204, description = "All tasks were deleted")),
+)]
+#[debug_handler(state = AppState)]
+pub async fn delete_tasks_batch(
+    State(pool): State<SqlitePool>,
+    State(scheduler): State<SchedulerHandle>,
+    Authentication(account_id, access): Authentication,
+    Json(batch_request): Json<DeleteTasksBatchRequest>,
+) -> Result<StatusCode, AppError> {
+    access.require(Scope::WriteTasks)?;
+    validate_batch_size(batch_request.ids.len())?;
+
+    // Checked in the transaction that writes, so access cannot change in between
+    let mut tx = pool.begin().await?;
+
+    let mut errors = Vec::new();
+    for (index, &id) in batch_request.ids.iter().enumerate() {
+        let result = if batch_request.ids[..index].contains(&id) {
+            Err(AppError::BadRequest(
+                "Task is already part of the batch".to_string(),
+            ))
+        } else {
+            check_removal(account_id, id, &mut tx).await
+        };
+
+        match result {
+            Ok(()) => {}
+            Err(error @ AppError::Internal(_)) => return Err(error),
+            Err(error) => errors.extend(error.into_field_errors(&format!("ids[{index}]"))),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    for id in batch_request.ids {
+        delete_task_row(id, &mut tx).await?;
+    }
+
+    tx.commit().await?;
+
+    scheduler.trigger();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn validate_batch_size(size: usize) -> Result<(), AppError> {
+    if size == 0 || size > MAX_BATCH_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "A batch must hold between 1 and {MAX_BATCH_SIZE} items"
+        )));
+    }
+
+    Ok(())
+}
+
+async fn insert_task(
+    account_id: i64,
+    access: &Access,
+    create_task_request: CreateTaskRequest,
+    pool: &SqlitePool,
+) -> Result<Task, AppError> {
+    access.require(Scope::WriteTasks)?;
+
+    let mut tx = pool.begin().await?;
+    let tags = check_task(account_id, &create_task_request, &mut tx).await?;
+    let task = store_task(create_task_request, tags, &mut tx).await?;
+    tx.commit().await?;
+
+    Ok(task)
+}
+
+// Everything that keeps a task from being created, returning its normalized tags if nothing does
+async fn check_task(
+    account_id: i64,
+    create_task_request: &CreateTaskRequest,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<String>, AppError> {
+    let role = device_role(account_id, create_task_request.device_id, &mut *conn)
+        .await?
+        .ok_or(AppError::NotFound("No device with id exists".to_string()))?;
+    require_role(role, Role::Member)?;
+
+    validate_timing(&create_task_request.timespan, create_task_request.duration)?;
+    validate_availability(
+        create_task_request.device_id,
+        &create_task_request.timespan,
+        create_task_request.duration,
+        conn,
+    )
+    .await?;
+
+    normalize_tags(&create_task_request.tags)
+}
+
+async fn store_task(
+    create_task_request: CreateTaskRequest,
+    tags: Vec<String>,
+    tx: &mut SqliteConnection,
+) -> Result<Task, AppError> {
+    let title = match create_task_request.title {
+        Some(title) => title,
+        None => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT name
+                FROM Devices
+                WHERE id = ?
+                "#,
+                create_task_request.device_id
+            )
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+
     let id = sqlx::query_scalar!(
         r#"
-        INSERT INTO Tasks (timespan_start, timespan_end, duration, device_id)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO Tasks (timespan_start, timespan_end, duration, device_id, title, notes)
+        VALUES (?, ?, ?, ?, ?, ?)
         RETURNING id
         "#,
         create_task_request.timespan.start,
         create_task_request.timespan.end,
         create_task_request.duration,
-        create_task_request.device_id
+        create_task_request.device_id,
+        title,
+        create_task_request.notes
     )
-    .fetch_one(&pool)
-    .await
-    .map_err(internal_error)?;
+    .fetch_one(&mut *tx)
+    .await?;
 
-    let task = Task {
+    set_tags(id, &tags, tx).await?;
+
+    Ok(Task {
         id,
-        timespan: Timespan {
-            start: create_task_request.timespan.start,
-            end: create_task_request.timespan.end,
-        },
+        timespan: create_task_request.timespan,
         duration: create_task_request.duration,
         device_id: create_task_request.device_id,
-    };
+        title,
+        notes: create_task_request.notes,
+        tags,
+    })
+}
+
+async fn remove_task(
+    account_id: i64,
+    access: &Access,
+    id: i64,
+    pool: &SqlitePool,
+) -> Result<(), AppError> {
+    access.require(Scope::WriteTasks)?;
+
+    let mut tx = pool.begin().await?;
+    check_removal(account_id, id, &mut tx).await?;
+    delete_task_row(id, &mut tx).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
 
-    Ok(Json(task))
+async fn check_removal(
+    account_id: i64,
+    id: i64,
+    conn: &mut SqliteConnection,
+) -> Result<(), AppError> {
+    let role = task_role(account_id, id, conn)
+        .await?
+        .ok_or(AppError::NotFound("No task with id exists".to_string()))?;
+    require_role(role, Role::Member)
 }
 
-#[debug_handler]
-pub async fn delete_task(
-    State(pool): State<SqlitePool>,
-    Authentication(account_id): Authentication,
-    Json(task): Json<Task>,
-) -> Result<(), (StatusCode, String)> {
-    sqlx::query!(
+async fn delete_task_row(id: i64, tx: &mut SqliteConnection) -> Result<(), AppError> {
+    let result = sqlx::query!(
         r#"
         DELETE FROM Tasks
-        WHERE id == ? AND EXISTS (
-            SELECT * 
-            FROM Tasks 
-            JOIN Devices ON Tasks.device_id == Devices.id 
-            AND Devices.account_id == ?
-        )
+        WHERE id == ?
+        "#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("No task with id exists".to_string()));
+    }
+
+    Ok(())
+}
+
+fn validate_timing(timespan: &Timespan, duration: Milliseconds) -> Result<(), AppError> {
+    if timespan.start >= timespan.end {
+        return Err(AppError::invalid(
+            "timespan",
+            "Timespan must start before it ends",
+        ));
+    }
+
+    // Availability is worked out day by day, so the days a timespan covers need a bound
+    let latest_end = timespan
+        .start
+        .checked_add_days(Days::new(MAX_TIMESPAN_DAYS));
+    if latest_end.is_some_and(|latest_end| timespan.end > latest_end) {
+        return Err(AppError::invalid(
+            "timespan",
+            format!("Timespan may be at most {MAX_TIMESPAN_DAYS} days long"),
+        ));
+    }
+
+    if duration <= Milliseconds::from(0) {
+        return Err(AppError::invalid("duration", "Duration must be positive"));
+    }
+
+    if duration > Milliseconds::from(timespan.duration()) {
+        return Err(AppError::invalid(
+            "duration",
+            "Duration does not fit within the timespan",
+        ));
+    }
+
+    Ok(())
+}
+
+// Rejects tasks the scheduler could never place because the device is unavailable throughout
+async fn validate_availability(
+    device_id: i64,
+    timespan: &Timespan,
+    duration: Milliseconds,
+    conn: &mut SqliteConnection,
+) -> Result<(), AppError> {
+    let availability = device_availability(device_id, conn).await?;
+
+    if availability
+        .earliest_run(timespan, duration.into())
+        .is_none()
+    {
+        return Err(AppError::invalid(
+            "timespan",
+            "The device is not available long enough within the timespan",
+        ));
+    }
+
+    Ok(())
+}
+
+// Trims tags and drops empty ones and repeats, which compare case-insensitively
+fn normalize_tags(tags: &[String]) -> Result<Vec<String>, AppError> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(AppError::invalid(
+                "tags",
+                format!("Tags may be at most {MAX_TAG_LENGTH} characters long"),
+            ));
+        }
+
+        if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+
+    normalized.sort_by_key(|t| t.to_lowercase());
+
+    Ok(normalized)
+}
+
+async fn task_tags<'e, E>(task_id: i64, executor: E) -> Result<Vec<String>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_scalar!(
+        r#"
+        SELECT tag
+        FROM TaskTags
+        WHERE task_id = ?
+        ORDER BY tag
         "#,
-        task.id,
-        account_id
+        task_id
     )
-    .execute(&pool)
+    .fetch_all(executor)
     .await
-    .map_err(internal_error)?;
+}
+
+async fn set_tags(
+    task_id: i64,
+    tags: &[String],
+    tx: &mut SqliteConnection,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        DELETE FROM TaskTags
+        WHERE task_id = ?
+        "#,
+        task_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for tag in tags {
+        sqlx::query!(
+            r#"
+            INSERT INTO TaskTags (task_id, tag)
+            VALUES (?, ?)
+            "#,
+            task_id,
+            tag
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
 
     Ok(())
 }
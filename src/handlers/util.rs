@@ -1,8 +1,99 @@
-use axum::http::StatusCode;
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
 
-pub fn internal_error<E>(err: E) -> (StatusCode, String)
-where
-    E: std::error::Error,
-{
-    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+use crate::{
+    error::AppError,
+    protocol::versioning::{ApiVersion, Versioned},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 1000;
+
+// When the resource routes replaced the old ones, 2026-10-19, in Unix seconds. No sunset
+// date is planned yet, so no `Sunset` header is sent.
+const ROUTES_DEPRECATED_AT: &str = "@1792368000";
+
+pub type Created<B> = (StatusCode, [(header::HeaderName, String); 1], B);
+
+pub type Page<T> = (HeaderMap, VersionedJson<Vec<T>>);
+
+/// JSON in the shape the API version of the request expects.
+pub struct VersionedJson<T>(pub ApiVersion, pub T);
+
+impl<T: Versioned> IntoResponse for VersionedJson<T> {
+    fn into_response(self) -> Response {
+        let VersionedJson(version, body) = self;
+
+        match version {
+            ApiVersion::V1 => Json(body.into_v1()).into_response(),
+            ApiVersion::V2 => Json(body).into_response(),
+        }
+    }
+}
+
+/// A 201 response pointing at `id` within the collection `collection_uri` was posted to.
+pub fn created<B>(collection_uri: &Uri, id: i64, body: B) -> Created<B> {
+    let location = format!("{}/{}", collection_uri.path().trim_end_matches('/'), id);
+
+    (StatusCode::CREATED, [(header::LOCATION, location)], body)
+}
+
+/// How many items to return per page for a requested `limit`.
+pub fn page_size(limit: Option<i64>) -> Result<i64, AppError> {
+    match limit {
+        None => Ok(DEFAULT_PAGE_SIZE),
+        Some(limit) if (1..=MAX_PAGE_SIZE).contains(&limit) => Ok(limit),
+        Some(_) => Err(AppError::invalid(
+            "limit",
+            format!("Limit must be between 1 and {MAX_PAGE_SIZE}"),
+        )),
+    }
+}
+
+/// The error for a cursor whose item has been deleted, since the page after it is unknown.
+pub fn stale_cursor() -> AppError {
+    AppError::invalid(
+        "cursor",
+        "The item this cursor continues from no longer exists, start again from the first page",
+    )
+}
+
+/// Responds with up to `page_size` of `items`, which should hold one more item if there
+/// is a next page. That page is linked to with the query `next` builds from the last item.
+pub fn page<T, Q: Serialize>(
+    version: ApiVersion,
+    uri: &Uri,
+    mut items: Vec<T>,
+    page_size: i64,
+    next: impl FnOnce(&T) -> Q,
+) -> Page<T> {
+    let mut headers = HeaderMap::new();
+
+    if items.len() as i64 > page_size {
+        items.truncate(page_size as usize);
+
+        let next_query = items.last().map(next).map(serde_urlencoded::to_string);
+        if let Some(Ok(query)) = next_query {
+            let link = format!("<{}?{}>; rel=\"next\"", uri.path(), query);
+            if let Ok(link) = HeaderValue::from_str(&link) {
+                headers.insert(header::LINK, link);
+            }
+        }
+    }
+
+    (headers, VersionedJson(version, items))
+}
+
+/// Flags responses from routes that have been superseded, see RFC 9745.
+pub async fn mark_deprecated(mut response: Response) -> Response {
+    response.headers_mut().insert(
+        "Deprecation",
+        HeaderValue::from_static(ROUTES_DEPRECATED_AT),
+    );
+
+    response
 }
@@ -0,0 +1,21 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Whether the service should still be sent traffic. Shared by clones.
+#[derive(Clone, Default)]
+pub struct Health {
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl Health {
+    /// Fails readiness from now on, so new traffic goes elsewhere while requests drain.
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Relaxed)
+    }
+}
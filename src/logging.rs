@@ -0,0 +1,26 @@
+use tracing::level_filters::LevelFilter;
+
+use crate::config::{Config, LogFormat, LogLevel};
+
+/// Sends log events to stdout in the configured format, from the configured level up.
+pub fn init(config: &Config) {
+    let level = match config.log_level {
+        LogLevel::Error => LevelFilter::ERROR,
+        LogLevel::Warn => LevelFilter::WARN,
+        LogLevel::Info => LevelFilter::INFO,
+        LogLevel::Debug => LevelFilter::DEBUG,
+        LogLevel::Trace => LevelFilter::TRACE,
+    };
+
+    let subscriber = tracing_subscriber::fmt().with_max_level(level);
+
+    match config.log_format {
+        LogFormat::Pretty => subscriber.init(),
+        // Fields of the request span go into every event logged within it
+        LogFormat::Json => subscriber
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    error::Error,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+
+/// Counting records scans whole tables, so scrapes in between reuse the last counts.
+const RECORDS_SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Everything exported at `/metrics`. Clones share the same collectors.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    logins: IntCounterVec,
+    scheduler_runs: IntCounterVec,
+    scheduler_run_duration: HistogramVec,
+    // Sampled when scraped rather than kept up to date
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+    records: IntGaugeVec,
+    records_sampled_at: Arc<Mutex<Option<Instant>>>,
+}
+
+/// How a login attempt ended, see [`Metrics::record_login`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginOutcome {
+    Success,
+    Failure,
+    LockedOut,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled, by route"),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time taken to respond to HTTP requests, by route",
+            ),
+            &["method", "route"],
+        )?;
+        let logins = IntCounterVec::new(
+            Opts::new("logins_total", "Login attempts, by outcome"),
+            &["outcome"],
+        )?;
+        let scheduler_runs = IntCounterVec::new(
+            Opts::new("scheduler_runs_total", "Times the schedule was recomputed"),
+            &["outcome"],
+        )?;
+        let scheduler_run_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "scheduler_run_duration_seconds",
+                "Time taken to recompute the schedule",
+            ),
+            &["outcome"],
+        )?;
+        let pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_connections",
+                "Open database connections, by whether they are in use",
+            ),
+            &["state"],
+        )?;
+        let pool_max_connections = IntGauge::new(
+            "db_pool_max_connections",
+            "Most database connections the pool will open",
+        )?;
+        let records = IntGaugeVec::new(
+            Opts::new("records", "Stored accounts, devices and tasks"),
+            &["kind"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(logins.clone()))?;
+        registry.register(Box::new(scheduler_runs.clone()))?;
+        registry.register(Box::new(scheduler_run_duration.clone()))?;
+        registry.register(Box::new(pool_connections.clone()))?;
+        registry.register(Box::new(pool_max_connections.clone()))?;
+        registry.register(Box::new(records.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            logins,
+            scheduler_runs,
+            scheduler_run_duration,
+            pool_connections,
+            pool_max_connections,
+            records,
+            records_sampled_at: Arc::default(),
+        })
+    }
+
+    /// `route` is the path the router matched, like `/v1/tasks/:id`, to keep ids out of labels.
+    pub fn record_http_request(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(latency.as_secs_f64());
+    }
+
+    pub fn record_login(&self, outcome: LoginOutcome) {
+        let outcome = match outcome {
+            LoginOutcome::Success => "success",
+            LoginOutcome::Failure => "failure",
+            LoginOutcome::LockedOut => "locked_out",
+        };
+
+        self.logins.with_label_values(&[outcome]).inc();
+    }
+
+    pub fn record_scheduler_run(&self, succeeded: bool, duration: Duration) {
+        let outcome = if succeeded { "success" } else { "failure" };
+
+        self.scheduler_runs.with_label_values(&[outcome]).inc();
+        self.scheduler_run_duration
+            .with_label_values(&[outcome])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Samples the pool and, at most once per [`RECORDS_SAMPLE_INTERVAL`], counts stored
+    /// records, then renders everything in the Prometheus text format.
+    pub async fn render(&self, pool: &SqlitePool) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let idle = pool.num_idle() as i64;
+        self.pool_connections.with_label_values(&["idle"]).set(idle);
+        self.pool_connections
+            .with_label_values(&["in_use"])
+            .set(i64::from(pool.size()) - idle);
+        self.pool_max_connections
+            .set(pool.options().get_max_connections().into());
+
+        self.sample_records(pool).await?;
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    async fn sample_records(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        // Held while counting so concurrent scrapes wait for one count instead of each running it
+        let mut sampled_at = self.records_sampled_at.lock().await;
+        if sampled_at.is_some_and(|at| at.elapsed() < RECORDS_SAMPLE_INTERVAL) {
+            return Ok(());
+        }
+
+        let counts = sqlx::query!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM Accounts) as "accounts!: i64",
+                (SELECT COUNT(*) FROM Devices) as "devices!: i64",
+                (SELECT COUNT(*) FROM Tasks) as "tasks!: i64"
+            "#
+        )
+        .fetch_one(pool)
+        .await?;
+
+        self.records
+            .with_label_values(&["accounts"])
+            .set(counts.accounts);
+        self.records
+            .with_label_values(&["devices"])
+            .set(counts.devices);
+        self.records.with_label_values(&["tasks"]).set(counts.tasks);
+        *sampled_at = Some(Instant::now());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+pub mod cors;
+pub mod metrics;
+pub mod rate_limit;
+pub mod rejections;
+pub mod request_id;
+pub mod trace;
@@ -0,0 +1,36 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::metrics::Metrics;
+
+/// Counts and times requests by the route they matched. Must be added as a route
+/// layer, since the matched route is only known once the router has picked one.
+pub async fn record_http_metrics(
+    State(metrics): State<Metrics>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    metrics.record_http_request(
+        method.as_str(),
+        &route,
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+
+    response
+}
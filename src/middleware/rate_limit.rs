@@ -0,0 +1,45 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    error::AppError, extractors::auth::Authentication, security::rate_limit::RateLimitKey,
+    state::AppState,
+};
+
+/// Limits how often each account, or each address for requests without valid
+/// credentials, may call the API. Responds 429 with `Retry-After` past the limit.
+///
+/// The authentication resolved here stays in the request, so handlers do not look it up again.
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (mut parts, body) = request.into_parts();
+
+    let key = match Authentication::from_request_parts(&mut parts, &state).await {
+        Ok(Authentication(account_id, _)) => RateLimitKey::Account(account_id),
+        Err(_) => {
+            let ip = connect_info
+                .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |ConnectInfo(address)| {
+                    address.ip()
+                });
+            RateLimitKey::Ip(ip)
+        }
+    };
+
+    if let Err(retry_after) = state.rate_limiter.check(key) {
+        return Err(AppError::too_many_requests(
+            "Too many requests",
+            retry_after,
+        ));
+    }
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
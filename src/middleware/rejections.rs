@@ -0,0 +1,28 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::error::AppError;
+
+/// Gives the responses tower layers produce on their own, like an oversized body or a
+/// request that took too long, the same JSON error body as every other error.
+pub async fn structure_rejections(response: Response) -> Response {
+    let structured = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type == "application/json");
+    if structured {
+        return response;
+    }
+
+    match response.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => {
+            AppError::PayloadTooLarge("Request body is too large".to_string()).into_response()
+        }
+        StatusCode::REQUEST_TIMEOUT => {
+            AppError::RequestTimeout("Request took too long to handle".to_string()).into_response()
+        }
+        _ => response,
+    }
+}
@@ -0,0 +1,48 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+// Longer ids from clients are replaced, so they cannot flood the logs
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Gives every request an id, returned in the `X-Request-Id` header and error bodies.
+/// An id the client sent along is kept, so requests can be followed across services.
+pub async fn assign_request_id(request: Request, next: Next) -> Response {
+    let request_id = incoming_request_id(request.headers())
+        .unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert("X-Request-Id", value);
+    }
+
+    response
+}
+
+fn incoming_request_id(headers: &HeaderMap) -> Option<String> {
+    let request_id = headers.get("X-Request-Id")?.to_str().ok()?;
+
+    let valid = !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+
+    valid.then(|| request_id.to_string())
+}
+
+/// The id of the request being handled, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
@@ -0,0 +1,45 @@
+use std::time::Instant;
+
+use axum::{extract::Request, middleware::Next, response::Response};
+use tracing::{field, Instrument};
+
+use super::request_id::current_request_id;
+
+/// Runs each request in a span, and logs how it went once there is a response.
+/// The account id is filled in by authentication, see [`record_account_id`].
+pub async fn trace_request(request: Request, next: Next) -> Response {
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        request_id = current_request_id().unwrap_or_default(),
+        account_id = field::Empty,
+        status = field::Empty,
+        latency_ms = field::Empty,
+    );
+
+    async move {
+        let started = Instant::now();
+        let response = next.run(request).await;
+        let latency = started.elapsed();
+
+        let span = tracing::Span::current();
+        span.record("status", response.status().as_u16());
+        span.record("latency_ms", latency.as_secs_f64() * 1000.0);
+
+        if response.status().is_server_error() {
+            tracing::error!("Request finished");
+        } else {
+            tracing::info!("Request finished");
+        }
+
+        response
+    }
+    .instrument(span)
+    .await
+}
+
+/// Notes which account the request being handled was made by.
+pub fn record_account_id(account_id: i64) {
+    tracing::Span::current().record("account_id", account_id);
+}
@@ -0,0 +1,125 @@
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, SecurityScheme},
+        ContentBuilder, RefOr, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+
+use crate::{error::ErrorBody, handlers};
+
+/// The OpenAPI document describing every route served by [`crate::app`].
+#[allow(deprecated)]
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Smart home scheduler",
+        description = "Schedules tasks on smart devices shared between household members"
+    ),
+    servers(
+        (url = "/v1", description = "Calls the `room` of devices their `location`"),
+        (url = "/v2", description = "Leaves out the superseded routes"),
+    ),
+    paths(
+        handlers::tasks::get_tasks,
+        handlers::tasks::get_all_tasks,
+        handlers::tasks::get_task,
+        handlers::tasks::create_task,
+        handlers::tasks::post_task,
+        handlers::tasks::update_task,
+        handlers::tasks::delete_task,
+        handlers::tasks::delete_task_by_id,
+        handlers::tasks::create_tasks_batch,
+        handlers::tasks::delete_tasks_batch,
+        handlers::devices::get_all_smart_devices,
+        handlers::devices::get_all_devices,
+        handlers::devices::get_smart_device,
+        handlers::devices::create_smart_device,
+        handlers::devices::post_smart_device,
+        handlers::devices::update_smart_device,
+        handlers::devices::get_device_availability,
+        handlers::devices::put_device_availability,
+        handlers::devices::delete_smart_device,
+        handlers::devices::delete_smart_device_by_id,
+        handlers::exclusion_groups::get_exclusion_groups,
+        handlers::exclusion_groups::get_exclusion_group,
+        handlers::exclusion_groups::post_exclusion_group,
+        handlers::exclusion_groups::update_exclusion_group,
+        handlers::exclusion_groups::delete_exclusion_group_by_id,
+        handlers::exclusion_groups::check_conflicts,
+        handlers::events::get_events,
+        handlers::households::get_households,
+        handlers::households::create_household,
+        handlers::households::create_invitation,
+        handlers::households::join_household,
+        handlers::households::leave_household,
+        handlers::api_keys::get_api_keys,
+        handlers::api_keys::create_api_key,
+        handlers::api_keys::revoke_api_key,
+        handlers::accounts::register_account,
+        handlers::accounts::login_to_account,
+        handlers::admin::get_accounts,
+        handlers::admin::disable_account,
+        handlers::admin::enable_account,
+        handlers::admin::revoke_account_tokens,
+        handlers::admin::trigger_reschedule,
+    ),
+    components(schemas(ErrorBody)),
+    modifiers(&SecuritySchemes, &ErrorResponses),
+    security(("auth_token" = []), ("api_key" = [])),
+)]
+pub struct ApiDoc;
+
+// Requests authenticate with either a login token or an API key
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "auth_token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Auth-Token"))),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+        );
+    }
+}
+
+// Every failure is reported as an `ErrorBody`, so document it once for all operations
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let response = ResponseBuilder::new()
+            .description("The request failed; see `code` for why")
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name(
+                        "ErrorBody",
+                    ))))
+                    .build(),
+            )
+            .build();
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ];
+
+            for operation in operations.into_iter().flatten() {
+                operation
+                    .responses
+                    .responses
+                    .insert("default".to_string(), RefOr::T(response.clone()));
+            }
+        }
+    }
+}
@@ -1,3 +1,11 @@
 pub mod accounts;
+pub mod admin;
+pub mod api_keys;
 pub mod devices;
+pub mod exclusion_groups;
+pub mod health;
+pub mod households;
+pub mod pagination;
 pub mod tasks;
+pub mod v1;
+pub mod versioning;
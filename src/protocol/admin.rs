@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AccountSummary {
+    pub id: i64,
+    pub username: String,
+    pub is_admin: bool,
+    pub disabled: bool,
+    // Devices and tasks registered by the account itself
+    pub device_count: i64,
+    pub task_count: i64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AccountRequest {
+    pub account_id: i64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RevokeTokensResponse {
+    pub auth_tokens: u64,
+    pub api_keys: u64,
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::data_model::api_key::{ApiKey, Scope};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CreateApiKeyResponse {
+    pub api_key: ApiKey,
+    // Only stored hashed, so this is the one chance to see it
+    pub key: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RevokeApiKeyRequest {
+    pub id: i64,
+}
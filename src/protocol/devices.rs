@@ -1,6 +1,81 @@
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize, Serialize)]
+use super::pagination::SortOrder;
+use crate::data_model::device::DeviceType;
+
+#[derive(Deserialize, Serialize, Default, ToSchema)]
 pub struct CreateDeviceRequest {
     pub effect: f64,
+    // Defaults to the label of the device type
+    pub name: Option<String>,
+    #[serde(default)]
+    pub device_type: DeviceType,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub room: Option<String>,
+    // Defaults to the account's personal household
+    pub household_id: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct DeleteDeviceRequest {
+    pub id: i64,
+}
+
+// Fields left out are kept as they are
+#[derive(Deserialize, Serialize, Default, ToSchema)]
+pub struct UpdateDeviceRequest {
+    pub effect: Option<f64>,
+    pub name: Option<String>,
+    pub device_type: Option<DeviceType>,
+    // `null` clears the metadata below, while leaving it out keeps it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub manufacturer: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub model: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub room: Option<Option<String>>,
+    pub household_id: Option<i64>,
+}
+
+/// Query parameters for listing devices, all filters are optional.
+#[derive(Deserialize, Serialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeviceQuery {
+    pub household_id: Option<i64>,
+    pub device_type: Option<DeviceType>,
+    #[serde(default)]
+    pub sort: DeviceSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    // The id of the last device of the previous page
+    pub cursor: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(
+    Deserialize, Serialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum DeviceSort {
+    #[default]
+    Id,
+    Name,
 }
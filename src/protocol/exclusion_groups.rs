@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::data_model::time::Timespan;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CreateExclusionGroupRequest {
+    pub name: String,
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: i64,
+    pub device_ids: Vec<i64>,
+    // Defaults to the account's personal household
+    pub household_id: Option<i64>,
+}
+
+fn default_max_concurrent() -> i64 {
+    1
+}
+
+// Fields left out are kept as they are, `device_ids` replaces all members when given
+#[derive(Deserialize, Serialize, Default, ToSchema)]
+pub struct UpdateExclusionGroupRequest {
+    pub name: Option<String>,
+    pub max_concurrent: Option<i64>,
+    pub device_ids: Option<Vec<i64>>,
+}
+
+/// A prospective run of `device_id` to check against the current schedule.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ConflictCheckRequest {
+    pub device_id: i64,
+    pub run: Timespan,
+}
+
+/// An exclusion group that would be over its limit, with the scheduled tasks filling it.
+#[derive(Deserialize, Serialize, Debug, PartialEq, ToSchema)]
+pub struct Conflict {
+    pub group_id: i64,
+    pub name: String,
+    pub max_concurrent: i64,
+    pub task_ids: Vec<i64>,
+}
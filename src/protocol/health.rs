@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub checks: ReadinessChecks,
+}
+
+// Every check has to pass for the service to be ready
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ReadinessChecks {
+    pub database: bool,
+    pub migrations: bool,
+    pub scheduler: bool,
+    pub shutting_down: bool,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::data_model::{
+    household::{Household, Role},
+    time::DateTimeUtc,
+};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CreateHouseholdRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct HouseholdMembership {
+    pub household: Household,
+    pub role: Role,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CreateInvitationRequest {
+    pub household_id: i64,
+    pub role: Role,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct Invitation {
+    pub code: String,
+    pub household_id: i64,
+    pub role: Role,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTimeUtc,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct JoinHouseholdRequest {
+    pub code: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct LeaveHouseholdRequest {
+    pub household_id: i64,
+}
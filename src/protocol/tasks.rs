@@ -1,10 +1,87 @@
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-use crate::data_model::time::{Milliseconds, Timespan};
+use super::pagination::SortOrder;
+use crate::data_model::{
+    task::TaskStatus,
+    time::{DateTimeUtc, Milliseconds, Timespan},
+};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct CreateTaskRequest {
     pub timespan: Timespan,
     pub duration: Milliseconds,
     pub device_id: i64,
+    // Defaults to the name of the device
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct DeleteTaskRequest {
+    pub id: i64,
+}
+
+// Fields left out are kept as they are
+#[derive(Deserialize, Serialize, Default, ToSchema)]
+pub struct UpdateTaskRequest {
+    pub timespan: Option<Timespan>,
+    pub duration: Option<Milliseconds>,
+    pub device_id: Option<i64>,
+    pub title: Option<String>,
+    // `null` clears the notes, while leaving them out keeps them
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub notes: Option<Option<String>>,
+    // Replaces all tags when given
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CreateTasksBatchRequest {
+    pub tasks: Vec<CreateTaskRequest>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct DeleteTasksBatchRequest {
+    pub ids: Vec<i64>,
+}
+
+/// Query parameters for listing tasks, all filters are optional.
+#[derive(Deserialize, Serialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskQuery {
+    pub device_id: Option<i64>,
+    // Only tasks whose timespan overlaps the range from `start` to `end`
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub start: Option<DateTimeUtc>,
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub end: Option<DateTimeUtc>,
+    pub status: Option<TaskStatus>,
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub sort: TaskSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    // The id of the last task of the previous page
+    pub cursor: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(
+    Deserialize, Serialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum TaskSort {
+    #[default]
+    Id,
+    Start,
+    End,
 }
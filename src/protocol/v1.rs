@@ -0,0 +1,209 @@
+// Shapes served and accepted by `/v1`, frozen so that changes to the data model do not break
+// its clients.
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    devices, tasks,
+    versioning::{Versioned, VersionedRequest},
+};
+use crate::data_model::{
+    self,
+    device::{DeviceType, DeviceTypeDefaults},
+    time::{Milliseconds, Timespan},
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Task {
+    pub id: i64,
+    pub timespan: Timespan,
+    pub duration: Milliseconds,
+    pub device_id: i64,
+    pub title: String,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl Versioned for data_model::task::Task {
+    type V1 = Task;
+
+    fn into_v1(self) -> Task {
+        Task {
+            id: self.id,
+            timespan: self.timespan,
+            duration: self.duration,
+            device_id: self.device_id,
+            title: self.title,
+            notes: self.notes,
+            tags: self.tags,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Device {
+    pub id: i64,
+    pub name: String,
+    pub device_type: DeviceType,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub location: Option<String>,
+    pub effect: f64,
+    pub account_id: i64,
+    pub household_id: i64,
+    pub defaults: DeviceTypeDefaults,
+}
+
+impl Versioned for data_model::device::Device {
+    type V1 = Device;
+
+    fn into_v1(self) -> Device {
+        Device {
+            id: self.id,
+            name: self.name,
+            device_type: self.device_type,
+            manufacturer: self.manufacturer,
+            model: self.model,
+            location: self.room,
+            effect: self.effect,
+            account_id: self.account_id,
+            household_id: self.household_id,
+            defaults: self.defaults,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateTaskRequest {
+    pub timespan: Timespan,
+    pub duration: Milliseconds,
+    pub device_id: i64,
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl VersionedRequest for tasks::CreateTaskRequest {
+    type V1 = CreateTaskRequest;
+}
+
+impl From<CreateTaskRequest> for tasks::CreateTaskRequest {
+    fn from(request: CreateTaskRequest) -> Self {
+        tasks::CreateTaskRequest {
+            timespan: request.timespan,
+            duration: request.duration,
+            device_id: request.device_id,
+            title: request.title,
+            notes: request.notes,
+            tags: request.tags,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateTaskRequest {
+    pub timespan: Option<Timespan>,
+    pub duration: Option<Milliseconds>,
+    pub device_id: Option<i64>,
+    pub title: Option<String>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub notes: Option<Option<String>>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl VersionedRequest for tasks::UpdateTaskRequest {
+    type V1 = UpdateTaskRequest;
+}
+
+impl From<UpdateTaskRequest> for tasks::UpdateTaskRequest {
+    fn from(request: UpdateTaskRequest) -> Self {
+        tasks::UpdateTaskRequest {
+            timespan: request.timespan,
+            duration: request.duration,
+            device_id: request.device_id,
+            title: request.title,
+            notes: request.notes,
+            tags: request.tags,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateTasksBatchRequest {
+    pub tasks: Vec<CreateTaskRequest>,
+}
+
+impl VersionedRequest for tasks::CreateTasksBatchRequest {
+    type V1 = CreateTasksBatchRequest;
+}
+
+impl From<CreateTasksBatchRequest> for tasks::CreateTasksBatchRequest {
+    fn from(request: CreateTasksBatchRequest) -> Self {
+        tasks::CreateTasksBatchRequest {
+            tasks: request.tasks.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateDeviceRequest {
+    pub effect: f64,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub device_type: DeviceType,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub location: Option<String>,
+    pub household_id: Option<i64>,
+}
+
+impl VersionedRequest for devices::CreateDeviceRequest {
+    type V1 = CreateDeviceRequest;
+}
+
+impl From<CreateDeviceRequest> for devices::CreateDeviceRequest {
+    fn from(request: CreateDeviceRequest) -> Self {
+        devices::CreateDeviceRequest {
+            effect: request.effect,
+            name: request.name,
+            device_type: request.device_type,
+            manufacturer: request.manufacturer,
+            model: request.model,
+            room: request.location,
+            household_id: request.household_id,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateDeviceRequest {
+    pub effect: Option<f64>,
+    pub name: Option<String>,
+    pub device_type: Option<DeviceType>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub manufacturer: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub model: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub location: Option<Option<String>>,
+    pub household_id: Option<i64>,
+}
+
+impl VersionedRequest for devices::UpdateDeviceRequest {
+    type V1 = UpdateDeviceRequest;
+}
+
+impl From<UpdateDeviceRequest> for devices::UpdateDeviceRequest {
+    fn from(request: UpdateDeviceRequest) -> Self {
+        devices::UpdateDeviceRequest {
+            effect: request.effect,
+            name: request.name,
+            device_type: request.device_type,
+            manufacturer: request.manufacturer,
+            model: request.model,
+            room: request.location,
+            household_id: request.household_id,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The versions of the API that are served, each under its own path prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    // What unversioned routes serve when the client does not ask for a version
+    pub const DEFAULT: ApiVersion = ApiVersion::V1;
+
+    pub fn from_number(number: u32) -> Option<Self> {
+        match number {
+            1 => Some(ApiVersion::V1),
+            2 => Some(ApiVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+/// A response body whose shape has changed between API versions. The type itself
+/// is what the latest version serves, older versions are converted into theirs.
+pub trait Versioned: Serialize {
+    type V1: Serialize;
+
+    fn into_v1(self) -> Self::V1;
+}
+
+impl<T: Versioned> Versioned for Vec<T> {
+    type V1 = Vec<T::V1>;
+
+    fn into_v1(self) -> Self::V1 {
+        self.into_iter().map(T::into_v1).collect()
+    }
+}
+
+/// A request body whose shape has changed between API versions. The type itself is
+/// what the latest version accepts, bodies sent to older versions are converted from theirs.
+pub trait VersionedRequest: DeserializeOwned + Send {
+    type V1: DeserializeOwned + Into<Self> + Send;
+}
@@ -0,0 +1,286 @@
+pub mod naive;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveTime, Utc};
+use sqlx::SqlitePool;
+use tokio::{sync::Notify, task::JoinHandle};
+
+use crate::{
+    data_model::{
+        availability::{AllowedHours, DeviceAvailability},
+        exclusion_group::ExclusionGroup,
+        task::Task,
+        time::{DateTimeUtc, Timespan},
+    },
+    metrics::Metrics,
+};
+
+// How often the schedule is recomputed when nothing asks for it sooner
+const RESCHEDULE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// When a task has been planned to start.
+pub struct Assignment {
+    pub task_id: i64,
+    pub start_time: DateTimeUtc,
+}
+
+/// Everything a scheduler has to take into account.
+pub struct Problem {
+    pub tasks: Vec<Task>,
+    // By device id, devices without an entry may run at any time
+    pub availability: HashMap<i64, DeviceAvailability>,
+    pub exclusion_groups: Vec<ExclusionGroup>,
+}
+
+impl Problem {
+    pub fn availability(&self, device_id: i64) -> &DeviceAvailability {
+        static ALWAYS: DeviceAvailability = DeviceAvailability {
+            allowed_hours: Vec::new(),
+            blackouts: Vec::new(),
+        };
+
+        self.availability.get(&device_id).unwrap_or(&ALWAYS)
+    }
+
+    /// The exclusion groups `device_id` belongs to.
+    pub fn exclusion_groups(&self, device_id: i64) -> impl Iterator<Item = &ExclusionGroup> {
+        self.exclusion_groups
+            .iter()
+            .filter(move |group| group.device_ids.contains(&device_id))
+    }
+}
+
+pub trait Scheduler: Send + Sync {
+    /// Plans a start time for each task that can be scheduled.
+    fn schedule(&self, problem: &Problem) -> Vec<Assignment>;
+}
+
+/// Wakes the background scheduler, see [`spawn_scheduler`].
+#[derive(Clone)]
+pub struct SchedulerHandle {
+    trigger: Arc<Notify>,
+    stop: Arc<Notify>,
+    // Taken when stopping, to wait for the task to end
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl SchedulerHandle {
+    /// Asks for the schedule to be recomputed, without waiting for it to happen.
+    pub fn trigger(&self) {
+        self.trigger.notify_one();
+    }
+
+    /// Whether the background task is still alive, it only ends by panicking or being stopped.
+    pub fn is_running(&self) -> bool {
+        let task = self.task.lock().unwrap_or_else(PoisonError::into_inner);
+        task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    /// Stops the background task, letting a run that has already started finish first.
+    pub async fn stop(&self) {
+        self.stop.notify_one();
+
+        let task = self
+            .task
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        if let Some(task) = task {
+            if let Err(e) = task.await {
+                tracing::error!(error = %e, "Scheduler ended abnormally");
+            }
+        }
+    }
+}
+
+/// Runs `scheduler` in the background whenever triggered and at a fixed interval.
+pub fn spawn_scheduler(
+    pool: SqlitePool,
+    scheduler: Arc<dyn Scheduler>,
+    metrics: Metrics,
+) -> SchedulerHandle {
+    let trigger = Arc::new(Notify::new());
+    let stop = Arc::new(Notify::new());
+    let (notified, stopped) = (trigger.clone(), stop.clone());
+
+    let task = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = notified.notified() => {}
+                _ = tokio::time::sleep(RESCHEDULE_INTERVAL) => {}
+                _ = stopped.notified() => break,
+            }
+
+            let started = Instant::now();
+            let result = reschedule(&pool, scheduler.as_ref()).await;
+            metrics.record_scheduler_run(result.is_ok(), started.elapsed());
+
+            if let Err(e) = result {
+                tracing::error!(error = %e, "Rescheduling failed");
+            }
+        }
+    });
+
+    SchedulerHandle {
+        trigger,
+        stop,
+        task: Arc::new(Mutex::new(Some(task))),
+    }
+}
+
+/// Replaces the events of all tasks that have not ended yet. Returns how many were scheduled.
+pub async fn reschedule(
+    pool: &SqlitePool,
+    scheduler: &dyn Scheduler,
+) -> Result<usize, sqlx::Error> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    let tasks = sqlx::query!(
+        r#"
+        SELECT id, timespan_start, timespan_end, duration, device_id, title, notes
+        FROM Tasks
+        WHERE timespan_end > ?
+        ORDER BY id
+        "#,
+        now
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let tasks: Vec<Task> = tasks
+        .into_iter()
+        .map(|t| Task {
+            id: t.id,
+            timespan: Timespan::new_from_naive(t.timespan_start, t.timespan_end),
+            duration: t.duration.into(),
+            device_id: t.device_id,
+            title: t.title,
+            notes: t.notes,
+            // Tags make no difference to when a task can run
+            tags: Vec::new(),
+        })
+        .collect();
+
+    for task in &tasks {
+        sqlx::query!(
+            r#"
+            DELETE FROM Events
+            WHERE task_id = ?
+            "#,
+            task.id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let mut availability: HashMap<i64, DeviceAvailability> = HashMap::new();
+
+    let allowed_hours = sqlx::query!(
+        r#"
+        SELECT device_id, start_time as "start: NaiveTime", end_time as "end: NaiveTime"
+        FROM DeviceAllowedHours
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for hours in allowed_hours {
+        availability
+            .entry(hours.device_id)
+            .or_default()
+            .allowed_hours
+            .push(AllowedHours {
+                start: hours.start,
+                end: hours.end,
+            });
+    }
+
+    let blackouts = sqlx::query!(
+        r#"
+        SELECT device_id, timespan_start, timespan_end
+        FROM DeviceBlackouts
+        WHERE timespan_end > ?
+        "#,
+        now
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for blackout in blackouts {
+        availability
+            .entry(blackout.device_id)
+            .or_default()
+            .blackouts
+            .push(Timespan::new_from_naive(
+                blackout.timespan_start,
+                blackout.timespan_end,
+            ));
+    }
+
+    let mut exclusion_groups: Vec<ExclusionGroup> = sqlx::query!(
+        r#"
+        SELECT id, household_id, name, max_concurrent
+        FROM ExclusionGroups
+        ORDER BY id
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|g| ExclusionGroup {
+        id: g.id,
+        household_id: g.household_id,
+        name: g.name,
+        max_concurrent: g.max_concurrent,
+        device_ids: Vec::new(),
+    })
+    .collect();
+
+    let members = sqlx::query!(
+        r#"
+        SELECT group_id, device_id
+        FROM ExclusionGroupDevices
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for member in members {
+        if let Some(group) = exclusion_groups
+            .iter_mut()
+            .find(|g| g.id == member.group_id)
+        {
+            group.device_ids.push(member.device_id);
+        }
+    }
+
+    let assignments = scheduler.schedule(&Problem {
+        tasks,
+        availability,
+        exclusion_groups,
+    });
+
+    for assignment in &assignments {
+        sqlx::query!(
+            r#"
+            INSERT INTO Events (task_id, start_time)
+            VALUES (?, ?)
+            "#,
+            assignment.task_id,
+            assignment.start_time
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(assignments.len())
+}
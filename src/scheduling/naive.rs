@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use super::{Assignment, Problem, Scheduler};
+use crate::data_model::time::Timespan;
+
+/// Starts every task as early as its timespan, the device's availability and its
+/// exclusion groups allow, in the order the tasks are given.
+pub struct NaiveScheduler;
+
+impl Scheduler for NaiveScheduler {
+    fn schedule(&self, problem: &Problem) -> Vec<Assignment> {
+        // The runs planned so far, by exclusion group id
+        let mut planned: HashMap<i64, Vec<Timespan>> = HashMap::new();
+        let mut assignments = Vec::new();
+
+        for task in &problem.tasks {
+            let availability = problem.availability(task.device_id);
+            let groups: Vec<_> = problem.exclusion_groups(task.device_id).collect();
+            let duration = task.duration.into();
+
+            let mut timespan = task.timespan;
+            let run = loop {
+                let Some(run) = availability.earliest_run(&timespan, duration) else {
+                    break None;
+                };
+
+                // A full group only frees up once one of the runs keeping it full has ended
+                let retry_from = groups
+                    .iter()
+                    .filter_map(|group| {
+                        let runs = planned.get(&group.id).map_or(&[][..], Vec::as_slice);
+                        (!group.admits(runs, &run)).then_some(runs)
+                    })
+                    .flatten()
+                    .filter(|r| r.overlaps(&run))
+                    .map(|r| r.end)
+                    .min();
+
+                match retry_from {
+                    Some(end) => timespan.start = end,
+                    None => break Some(run),
+                }
+            };
+
+            let Some(run) = run else {
+                continue;
+            };
+
+            for group in groups {
+                planned.entry(group.id).or_default().push(run);
+            }
+
+            assignments.push(Assignment {
+                task_id: task.id,
+                start_time: run.start,
+            });
+        }
+
+        assignments
+    }
+}
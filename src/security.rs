@@ -0,0 +1,4 @@
+pub mod authorization;
+pub mod login_attempts;
+pub mod password;
+pub mod rate_limit;
@@ -0,0 +1,102 @@
+use sqlx::{Sqlite, SqlitePool};
+
+use crate::{data_model::household::Role, error::AppError};
+
+/// The role `account_id` has in `household_id`, or `None` if it is not a member.
+pub async fn household_role(
+    account_id: i64,
+    household_id: i64,
+    pool: &SqlitePool,
+) -> Result<Option<Role>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT role as "role: Role"
+        FROM HouseholdMembers
+        WHERE account_id = ? AND household_id = ?
+        "#,
+        account_id,
+        household_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// The role `account_id` has in the household owning `device_id`, or `None` if the
+/// device does not exist or belongs to a household the account is not a member of.
+pub async fn device_role<'e, E>(
+    account_id: i64,
+    device_id: i64,
+    executor: E,
+) -> Result<Option<Role>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_scalar!(
+        r#"
+        SELECT HouseholdMembers.role as "role: Role"
+        FROM Devices
+        JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ? AND Devices.id = ?
+        "#,
+        account_id,
+        device_id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+/// The role `account_id` has in the household owning the device `task_id` runs on, or
+/// `None` if the task does not exist or is not visible to the account.
+pub async fn task_role<'e, E>(
+    account_id: i64,
+    task_id: i64,
+    executor: E,
+) -> Result<Option<Role>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_scalar!(
+        r#"
+        SELECT HouseholdMembers.role as "role: Role"
+        FROM Tasks
+        JOIN Devices ON Tasks.device_id == Devices.id
+        JOIN HouseholdMembers ON Devices.household_id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ? AND Tasks.id = ?
+        "#,
+        account_id,
+        task_id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+/// The role `account_id` has in the household owning the exclusion group `group_id`, or
+/// `None` if the group does not exist or is not visible to the account.
+pub async fn exclusion_group_role(
+    account_id: i64,
+    group_id: i64,
+    pool: &SqlitePool,
+) -> Result<Option<Role>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT HouseholdMembers.role as "role: Role"
+        FROM ExclusionGroups
+        JOIN HouseholdMembers ON ExclusionGroups.household_id == HouseholdMembers.household_id
+        WHERE HouseholdMembers.account_id = ? AND ExclusionGroups.id = ?
+        "#,
+        account_id,
+        group_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub fn require_role(role: Role, required: Role) -> Result<(), AppError> {
+    if role >= required {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden(
+            "Your role in the household does not allow this".to_string(),
+        ))
+    }
+}
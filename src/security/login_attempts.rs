@@ -0,0 +1,151 @@
+use std::net::IpAddr;
+
+use chrono::{Days, Duration, TimeZone, Utc};
+use sqlx::SqlitePool;
+
+// Lockout after the first failure past the free attempts, doubled for every further failure
+const BASE_LOCKOUT_SECONDS: i64 = 1;
+const MAX_LOCKOUT_SECONDS: i64 = 60 * 60;
+// Failures are forgotten once a key has had none for this long. Well past the longest
+// lockout, so a lockout always runs out before its failures do.
+const FAILURE_DECAY_DAYS: u64 = 1;
+
+/// Something failed logins are counted against, e.g. a username or a client address.
+#[derive(Clone)]
+pub struct AttemptKey {
+    key: String,
+    free_attempts: i64,
+}
+
+impl AttemptKey {
+    pub fn username(username: &str) -> Self {
+        AttemptKey {
+            key: format!("username:{username}"),
+            free_attempts: 5,
+        }
+    }
+
+    // Many users can share an address, so it is allowed more failures than a single username
+    pub fn ip(ip: IpAddr) -> Self {
+        AttemptKey {
+            key: format!("ip:{ip}"),
+            free_attempts: 20,
+        }
+    }
+
+    fn lockout_after(&self, failures: i64) -> Option<Duration> {
+        let excess = failures - self.free_attempts;
+        if excess <= 0 {
+            return None;
+        }
+
+        let factor = 1_i64
+            .checked_shl((excess - 1).min(32) as u32)
+            .unwrap_or(i64::MAX);
+        let seconds = BASE_LOCKOUT_SECONDS
+            .saturating_mul(factor)
+            .min(MAX_LOCKOUT_SECONDS);
+
+        Duration::try_seconds(seconds)
+    }
+}
+
+/// Returns how long the caller has to wait before any of `keys` may attempt a login again.
+pub async fn remaining_lockout(
+    keys: &[AttemptKey],
+    pool: &SqlitePool,
+) -> Result<Option<Duration>, sqlx::Error> {
+    let now = Utc::now();
+    let mut remaining: Option<Duration> = None;
+
+    for key in keys {
+        let locked_until = sqlx::query_scalar!(
+            r#"
+            SELECT locked_until
+            FROM LoginAttempts
+            WHERE attempt_key = ?
+            "#,
+            key.key
+        )
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+
+        if let Some(locked_until) = locked_until {
+            let wait = Utc.from_utc_datetime(&locked_until) - now;
+            if wait > Duration::zero() {
+                remaining = remaining.max(Some(wait));
+            }
+        }
+    }
+
+    Ok(remaining)
+}
+
+pub async fn record_failure(keys: &[AttemptKey], pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let decayed_before = now - Days::new(FAILURE_DECAY_DAYS);
+
+    // Keeps keys that were only ever tried a few times, like guessed usernames, from piling up
+    sqlx::query!(
+        r#"
+        DELETE FROM LoginAttempts
+        WHERE last_failure < ?
+        "#,
+        decayed_before
+    )
+    .execute(pool)
+    .await?;
+
+    for key in keys {
+        let failures = sqlx::query_scalar!(
+            r#"
+            INSERT INTO LoginAttempts (attempt_key, failures, last_failure)
+            VALUES (?, 1, ?)
+            ON CONFLICT(attempt_key) DO UPDATE SET
+                failures = CASE WHEN last_failure < ? THEN 1 ELSE failures + 1 END,
+                last_failure = excluded.last_failure
+            RETURNING failures
+            "#,
+            key.key,
+            now,
+            decayed_before
+        )
+        .fetch_one(pool)
+        .await?;
+
+        if let Some(lockout) = key.lockout_after(failures) {
+            let locked_until = now + lockout;
+
+            sqlx::query!(
+                r#"
+                UPDATE LoginAttempts
+                SET locked_until = ?
+                WHERE attempt_key = ?
+                "#,
+                locked_until,
+                key.key
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Only ever called with the username. Clearing the address as well would let anyone reset
+// its count by logging into an account of their own, so it is left to decay instead.
+pub async fn record_success(key: &AttemptKey, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM LoginAttempts
+        WHERE attempt_key = ?
+        "#,
+        key.key
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
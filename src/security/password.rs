@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use argon2::{
+    password_hash::{self, rand_core::OsRng, SaltString},
+    Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
+};
+
+/// Hashes and verifies account passwords with the configured Argon2 parameters.
+#[derive(Clone)]
+pub struct PasswordHashing {
+    argon2: Argon2<'static>,
+    // Verified against when an account does not exist, so it costs the same as a real one
+    dummy_hash: Arc<str>,
+}
+
+impl PasswordHashing {
+    pub fn new(params: Params) -> Result<Self, password_hash::Error> {
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = SaltString::generate(&mut OsRng);
+        let dummy_hash = argon2.hash_password(b"dummy password", &salt)?.to_string();
+
+        Ok(PasswordHashing {
+            argon2,
+            dummy_hash: dummy_hash.into(),
+        })
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, password_hash::Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = self.argon2.hash_password(password.as_bytes(), &salt)?;
+        Ok(hash.to_string())
+    }
+
+    /// Checks `password` against `hash`, or against a dummy hash if there is none.
+    pub fn verify(&self, password: &str, hash: Option<&str>) -> Result<bool, password_hash::Error> {
+        let hash = PasswordHash::new(hash.unwrap_or(&self.dummy_hash))?;
+
+        match self.argon2.verify_password(password.as_bytes(), &hash) {
+            Ok(()) => Ok(true),
+            Err(password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether `hash` was produced with something other than the configured algorithm and parameters.
+    pub fn needs_rehash(&self, hash: &str) -> Result<bool, password_hash::Error> {
+        let hash = PasswordHash::new(hash)?;
+        if hash.algorithm != Algorithm::Argon2id.ident()
+            || hash.version != Some(Version::V0x13.into())
+        {
+            return Ok(true);
+        }
+
+        let params = Params::try_from(&hash)?;
+        let configured = self.argon2.params();
+
+        Ok(params.m_cost() != configured.m_cost()
+            || params.t_cost() != configured.t_cost()
+            || params.p_cost() != configured.p_cost())
+    }
+}
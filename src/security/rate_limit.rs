@@ -0,0 +1,71 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+// Past this many tracked clients, buckets that have filled up again are forgotten
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Who requests are counted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Account(i64),
+    // Requests that are not authenticated, or whose credentials are invalid
+    Ip(IpAddr),
+}
+
+/// Token buckets refilling at a steady rate, one per client. Clones share the buckets.
+#[derive(Clone)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<HashMap<RateLimitKey, Bucket>>>,
+    capacity: f64,
+    per_second: f64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Allows `per_minute` requests a minute, of which up to `burst` at once.
+    pub fn new(per_minute: u32, burst: u32) -> Self {
+        RateLimiter {
+            buckets: Default::default(),
+            capacity: burst.into(),
+            per_second: f64::from(per_minute) / 60.0,
+        }
+    }
+
+    /// Takes a token for a request by `key`, or says how long to wait until there is one.
+    pub fn check(&self, key: RateLimitKey) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.capacity);
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - bucket.tokens;
+            Err(Duration::from_secs_f64(missing / self.per_second))
+        }
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.per_second).min(self.capacity)
+    }
+}
@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use sqlx::SqlitePool;
+
+use crate::{
+    config::Config,
+    health::Health,
+    metrics::Metrics,
+    scheduling::SchedulerHandle,
+    security::{password::PasswordHashing, rate_limit::RateLimiter},
+};
+
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub pool: SqlitePool,
+    pub password_hashing: PasswordHashing,
+    pub scheduler: SchedulerHandle,
+    pub config: Arc<Config>,
+    pub metrics: Metrics,
+    pub health: Health,
+    pub rate_limiter: RateLimiter,
+}